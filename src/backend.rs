@@ -0,0 +1,222 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bmbf_utils::{
+    BMBF_PLAYLISTS_PATH, BMBF_SONGS_PATH, PC_PLAYLISTS_PATH, PC_SONGS_PATH, PC_WIP_SONGS_PATH,
+    PLAYLISTS_PATH, SONGS_PATH, WIP_LEVEL_PREFIX,
+};
+
+/// Modification time (milliseconds since the Unix epoch) and size of a file,
+/// used to tell whether a cached level is still current.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FileStamp {
+    pub modified: u128,
+    pub size: u64,
+}
+
+impl From<fs::Metadata> for FileStamp {
+    fn from(metadata: fs::Metadata) -> Self {
+        FileStamp {
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_millis())
+                .unwrap_or(0),
+            size: metadata.len(),
+        }
+    }
+}
+
+/// Storage the level scanner and playlist functions read from and write to.
+///
+/// Levels are addressed by their folder name and playlists by their file name,
+/// both relative to the directories the backend was created for.
+pub trait LibraryBackend: Send + Sync {
+    /// Identifies the device or folder, so caches and backups of different libraries stay apart.
+    fn id(&self) -> String;
+    fn list_levels(&self) -> io::Result<Vec<String>>;
+    fn level_modified(&self, level: &str) -> io::Result<SystemTime>;
+    fn info_dat_stamp(&self, level: &str) -> io::Result<FileStamp>;
+    fn read_info_dat(&self, level: &str) -> io::Result<String>;
+    fn read_level_file(&self, level: &str, file_name: &str) -> io::Result<Vec<u8>>;
+    fn list_playlists(&self) -> io::Result<Vec<String>>;
+    fn read_playlist(&self, file_name: &str) -> io::Result<Vec<u8>>;
+    fn write_playlist(&self, file_name: &str, contents: &[u8]) -> io::Result<()>;
+    fn delete_playlist(&self, file_name: &str) -> io::Result<()>;
+
+    /// Songs and playlists directories on the local filesystem, if there are any to watch.
+    fn watch_paths(&self) -> Option<WatchPaths> {
+        None
+    }
+}
+
+/// Local directories a backend reads from.
+pub struct WatchPaths {
+    /// Level directories, each with the prefix the names of its levels get.
+    pub levels: Vec<(PathBuf, String)>,
+    pub playlists: PathBuf,
+}
+
+/// Where a library keeps its levels and playlists, relative to its root.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// SongLoader and PlaylistManager mods on the headset's shared storage.
+    #[default]
+    Quest,
+    /// A PC Beat Saber install, including work-in-progress levels.
+    Pc,
+    /// BMBFData on the headset's shared storage, used by older BMBF installs.
+    Bmbf,
+}
+
+/// Backend over plain directories: a mounted headset, a copied backup or a fixture tree.
+pub struct LocalBackend {
+    songs_path: PathBuf,
+    wip_songs_path: Option<PathBuf>,
+    playlists_path: PathBuf,
+}
+
+impl LocalBackend {
+    pub fn new(songs_path: impl Into<PathBuf>, playlists_path: impl Into<PathBuf>) -> Self {
+        LocalBackend {
+            songs_path: songs_path.into(),
+            wip_songs_path: None,
+            playlists_path: playlists_path.into(),
+        }
+    }
+
+    /// Also lists the levels in `wip_songs_path`, named with [`WIP_LEVEL_PREFIX`].
+    pub fn with_wip_songs(mut self, wip_songs_path: impl Into<PathBuf>) -> Self {
+        self.wip_songs_path = Some(wip_songs_path.into());
+        self
+    }
+
+    /// Uses the Quest SongLoader/PlaylistManager layout under `root`.
+    pub fn from_root(root: impl AsRef<Path>) -> Self {
+        LocalBackend::from_layout(root, Layout::Quest)
+    }
+
+    pub fn from_layout(root: impl AsRef<Path>, layout: Layout) -> Self {
+        let root = root.as_ref();
+        match layout {
+            Layout::Quest => LocalBackend::new(root.join(SONGS_PATH), root.join(PLAYLISTS_PATH)),
+            Layout::Pc => LocalBackend::new(root.join(PC_SONGS_PATH), root.join(PC_PLAYLISTS_PATH))
+                .with_wip_songs(root.join(PC_WIP_SONGS_PATH)),
+            Layout::Bmbf => {
+                LocalBackend::new(root.join(BMBF_SONGS_PATH), root.join(BMBF_PLAYLISTS_PATH))
+            }
+        }
+    }
+
+    pub fn songs_path(&self) -> &Path {
+        &self.songs_path
+    }
+
+    pub fn playlists_path(&self) -> &Path {
+        &self.playlists_path
+    }
+
+    fn level_path(&self, level: &str) -> PathBuf {
+        match (&self.wip_songs_path, level.strip_prefix(WIP_LEVEL_PREFIX)) {
+            (Some(wip_songs_path), Some(folder)) => wip_songs_path.join(folder),
+            _ => self.songs_path.join(level),
+        }
+    }
+}
+
+impl LibraryBackend for LocalBackend {
+    fn id(&self) -> String {
+        self.songs_path.to_string_lossy().into_owned()
+    }
+
+    fn list_levels(&self) -> io::Result<Vec<String>> {
+        let mut levels = list_file_names(&self.songs_path)?;
+
+        if let Some(wip_songs_path) = &self.wip_songs_path {
+            match list_file_names(wip_songs_path) {
+                Ok(wip_levels) => levels.extend(
+                    wip_levels
+                        .into_iter()
+                        .map(|folder| format!("{}{}", WIP_LEVEL_PREFIX, folder)),
+                ),
+                // Installs without WIP levels don't have the folder.
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(levels)
+    }
+
+    fn level_modified(&self, level: &str) -> io::Result<SystemTime> {
+        fs::metadata(self.level_path(level))?.modified()
+    }
+
+    fn info_dat_stamp(&self, level: &str) -> io::Result<FileStamp> {
+        let level_path = self.level_path(level);
+        fs::metadata(level_path.join("Info.dat"))
+            .or_else(|_| fs::metadata(level_path.join("info.dat")))
+            .map(FileStamp::from)
+    }
+
+    fn read_info_dat(&self, level: &str) -> io::Result<String> {
+        let level_path = self.level_path(level);
+        fs::read_to_string(level_path.join("Info.dat"))
+            .or_else(|_| fs::read_to_string(level_path.join("info.dat")))
+    }
+
+    fn read_level_file(&self, level: &str, file_name: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.level_path(level).join(file_name))
+    }
+
+    fn list_playlists(&self) -> io::Result<Vec<String>> {
+        list_file_names(&self.playlists_path)
+    }
+
+    fn read_playlist(&self, file_name: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.playlists_path.join(file_name))
+    }
+
+    fn write_playlist(&self, file_name: &str, contents: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.playlists_path)?;
+        // Write next to the target and rename, so an interrupted transfer never
+        // leaves a truncated playlist behind.
+        let temp_path = self.playlists_path.join(format!(".{}.tmp", file_name));
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, self.playlists_path.join(file_name)).inspect_err(|_| {
+            let _ = fs::remove_file(&temp_path);
+        })
+    }
+
+    fn delete_playlist(&self, file_name: &str) -> io::Result<()> {
+        fs::remove_file(self.playlists_path.join(file_name))
+    }
+
+    fn watch_paths(&self) -> Option<WatchPaths> {
+        let mut levels = vec![(self.songs_path.clone(), String::new())];
+        levels.extend(
+            self.wip_songs_path
+                .iter()
+                .map(|path| (path.clone(), WIP_LEVEL_PREFIX.to_string())),
+        );
+
+        Some(WatchPaths {
+            levels,
+            playlists: self.playlists_path.clone(),
+        })
+    }
+}
+
+fn list_file_names(path: &Path) -> io::Result<Vec<String>> {
+    Ok(fs::read_dir(path)?
+        .flatten()
+        .filter_map(|dir_entry| dir_entry.file_name().to_str().map(str::to_owned))
+        .collect())
+}
@@ -0,0 +1,153 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{backend::LibraryBackend, paths};
+
+const MAX_SNAPSHOTS: usize = 20;
+
+/// Previous versions of the playlists overwritten by one save.
+/// `created` is in milliseconds since the Unix epoch.
+pub struct Snapshot {
+    pub created: u64,
+    pub path: PathBuf,
+    pub file_names: Vec<String>,
+}
+
+impl Snapshot {
+    pub fn date(&self) -> String {
+        format_timestamp(self.created / 1000)
+    }
+}
+
+/// Copies the current contents of `file_names` into a new snapshot folder.
+///
+/// Playlists that don't exist yet are skipped, and no folder is created when
+/// there is nothing to back up. Old snapshots beyond the limit are pruned.
+pub fn create_snapshot(
+    backend: &dyn LibraryBackend,
+    file_names: &[String],
+) -> io::Result<Option<PathBuf>> {
+    let existing = backend.list_playlists().unwrap_or_default();
+    let previous_versions: Vec<(&String, Vec<u8>)> = file_names
+        .iter()
+        .filter(|file_name| existing.contains(file_name))
+        .map(|file_name| {
+            backend
+                .read_playlist(file_name)
+                .map(|content| (file_name, content))
+        })
+        .collect::<io::Result<_>>()?;
+
+    if previous_versions.is_empty() {
+        return Ok(None);
+    }
+
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = backup_dir(backend).join(created.to_string());
+    fs::create_dir_all(&path)?;
+
+    for (file_name, content) in previous_versions {
+        fs::write(path.join(file_name), content)?;
+    }
+
+    prune_snapshots(backend);
+
+    Ok(Some(path))
+}
+
+/// Snapshots are kept per device under `$XDG_DATA_HOME/playlist_creator/backups`.
+pub fn backup_dir(backend: &dyn LibraryBackend) -> PathBuf {
+    paths::data_dir()
+        .join("backups")
+        .join(paths::device_key(&backend.id()))
+}
+
+/// Lists snapshots of the backend's playlists, newest first.
+pub fn list_snapshots(backend: &dyn LibraryBackend) -> Vec<Snapshot> {
+    let mut snapshots: Vec<Snapshot> = match fs::read_dir(backup_dir(backend)) {
+        Ok(read_dir) => read_dir
+            .flatten()
+            .filter_map(|dir_entry| {
+                let created = dir_entry.file_name().to_str()?.parse::<u64>().ok()?;
+                let file_names = fs::read_dir(dir_entry.path())
+                    .ok()?
+                    .flatten()
+                    .filter_map(|file| file.file_name().to_str().map(str::to_owned))
+                    .collect();
+
+                Some(Snapshot {
+                    created,
+                    path: dir_entry.path(),
+                    file_names,
+                })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.created));
+    snapshots
+}
+
+/// Writes every playlist of the snapshot back, backing up the versions it replaces first.
+pub fn restore_snapshot(backend: &dyn LibraryBackend, snapshot: &Snapshot) -> io::Result<()> {
+    let contents: Vec<(&String, Vec<u8>)> = snapshot
+        .file_names
+        .iter()
+        .map(|file_name| {
+            fs::read(snapshot.path.join(file_name)).map(|content| (file_name, content))
+        })
+        .collect::<io::Result<_>>()?;
+
+    create_snapshot(backend, &snapshot.file_names)?;
+
+    for (file_name, content) in contents {
+        backend.write_playlist(file_name, &content)?;
+    }
+
+    Ok(())
+}
+
+fn prune_snapshots(backend: &dyn LibraryBackend) {
+    list_snapshots(backend)
+        .iter()
+        .skip(MAX_SNAPSHOTS)
+        .for_each(|snapshot| {
+            // One that can't be removed is simply tried again on the next save.
+            let _ = fs::remove_dir_all(&snapshot.path);
+        });
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time.
+fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // Civil-from-days conversion, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
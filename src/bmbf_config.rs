@@ -0,0 +1,70 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    bmbf_utils::{file_name_safe, Playlist, Song, CUSTOM_LEVEL_PREFIX},
+    error::LibraryError,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BmbfConfig {
+    #[serde(default)]
+    playlists: Vec<BmbfPlaylist>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BmbfPlaylist {
+    #[serde(rename = "PlaylistID")]
+    playlist_id: String,
+    playlist_name: String,
+    /// Base64 encoded PNG.
+    cover_image_bytes: Option<String>,
+    #[serde(default)]
+    song_list: Vec<BmbfSong>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BmbfSong {
+    #[serde(rename = "SongID")]
+    song_id: String,
+    #[serde(default)]
+    song_name: String,
+    level_author_name: Option<String>,
+}
+
+/// Reads the playlists embedded in a BMBF `config.json` as new, unsaved
+/// playlists. Songs that aren't custom levels, like the built-in ones, are left out.
+pub fn read_bmbf_playlists(path: &Path) -> Result<Vec<Playlist>, LibraryError> {
+    let contents = fs::read_to_string(path).map_err(LibraryError::ReadBmbfConfig)?;
+    let config: BmbfConfig =
+        serde_json::from_str(&contents).map_err(LibraryError::ParseBmbfConfig)?;
+
+    Ok(config
+        .playlists
+        .into_iter()
+        .map(|bmbf_playlist| {
+            let mut playlist = Playlist::new(&bmbf_playlist.playlist_name);
+            playlist.file_name = format!("{}.json", file_name_safe(&bmbf_playlist.playlist_id));
+            playlist.image = bmbf_playlist.cover_image_bytes;
+            playlist.songs = bmbf_playlist
+                .song_list
+                .into_iter()
+                .filter_map(|bmbf_song| {
+                    let hash = bmbf_song.song_id.strip_prefix(CUSTOM_LEVEL_PREFIX)?;
+                    Some(Song {
+                        hash: hash.to_uppercase(),
+                        name: bmbf_song.song_name,
+                        level_id: Some(format!("{}{}", CUSTOM_LEVEL_PREFIX, hash.to_uppercase())),
+                        level_author: bmbf_song.level_author_name,
+                        ..Default::default()
+                    })
+                })
+                .collect();
+            playlist
+        })
+        .collect())
+}
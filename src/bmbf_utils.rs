@@ -1,17 +1,38 @@
 use std::{
     collections::HashMap,
-    fs::{self, read_dir, File},
-    io::Write,
-    time::{Duration, SystemTime},
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{FileStamp, LibraryBackend},
+    backup,
+    error::{LibraryError, LoadReport},
+    info_dat::parse_info_dat,
+    paths,
+    playlist_format::{decode_playlist, encode_playlist, PlaylistFormat},
+};
 
 pub const BASE_PATH: &str = "/run/user/1000/gvfs";
 pub const SONGS_PATH: &str =
-    "/Internal shared storage/ModData/com.beatgames.beatsaber/Mods/SongLoader/CustomLevels";
+    "Internal shared storage/ModData/com.beatgames.beatsaber/Mods/SongLoader/CustomLevels";
 pub const PLAYLISTS_PATH: &str =
-    "/Internal shared storage/ModData/com.beatgames.beatsaber/Mods/PlaylistManager/Playlists";
+    "Internal shared storage/ModData/com.beatgames.beatsaber/Mods/PlaylistManager/Playlists";
+pub const BMBF_SONGS_PATH: &str = "Internal shared storage/BMBFData/CustomSongs";
+pub const BMBF_PLAYLISTS_PATH: &str = "Internal shared storage/BMBFData/Playlists";
+/// BMBF's config, older BMBF versions kept their playlists in it instead of in separate files.
+pub const BMBF_CONFIG_PATH: &str = "Internal shared storage/BMBFData/config.json";
+pub const PC_SONGS_PATH: &str = "Beat Saber_Data/CustomLevels";
+pub const PC_WIP_SONGS_PATH: &str = "Beat Saber_Data/CustomWIPLevels";
+pub const PC_PLAYLISTS_PATH: &str = "Playlists";
+/// Folder names of work-in-progress levels start with this, level folder names can't contain `/`.
+pub const WIP_LEVEL_PREFIX: &str = "CustomWIPLevels/";
+/// Level IDs of custom levels are this followed by the level hash.
+pub const CUSTOM_LEVEL_PREFIX: &str = "custom_level_";
 
 #[derive(Deserialize, Serialize, Default, Clone)]
 pub struct CustomLevel {
@@ -31,6 +52,86 @@ pub struct CustomLevel {
     pub beats_per_minute: f32,
     pub hash: Option<String>,
     pub modified: Option<u128>,
+    pub folder: Option<String>,
+    #[serde(alias = "_difficultyBeatmapSets", default)]
+    pub beatmap_sets: Vec<BeatmapSet>,
+    pub audio_data_file_name: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct BeatmapSet {
+    #[serde(alias = "_beatmapCharacteristicName")]
+    pub characteristic: String,
+    #[serde(alias = "_difficultyBeatmaps", default)]
+    pub beatmaps: Vec<Beatmap>,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct Beatmap {
+    #[serde(alias = "_difficulty")]
+    pub difficulty: String,
+    #[serde(alias = "_difficultyRank", default)]
+    pub difficulty_rank: u8,
+    #[serde(alias = "_beatmapFilename")]
+    pub file_name: String,
+    pub lightshow_file_name: Option<String>,
+    #[serde(alias = "_noteJumpMovementSpeed", default)]
+    pub note_jump_speed: f32,
+    #[serde(alias = "_noteJumpStartBeatOffset", default)]
+    pub note_jump_offset: f32,
+}
+
+impl CustomLevel {
+    pub fn beatmaps(&self) -> impl Iterator<Item = (&str, &Beatmap)> {
+        self.beatmap_sets.iter().flat_map(|set| {
+            set.beatmaps
+                .iter()
+                .map(|beatmap| (set.characteristic.as_str(), beatmap))
+        })
+    }
+
+    /// Files that go into the level hash after Info.dat, without repeats.
+    pub fn hashed_files(&self) -> Vec<&str> {
+        let mut files = Vec::<&str>::new();
+        let candidates = self
+            .audio_data_file_name
+            .iter()
+            .chain(self.beatmaps().flat_map(|(_, beatmap)| {
+                std::iter::once(&beatmap.file_name).chain(beatmap.lightshow_file_name.iter())
+            }));
+
+        for file in candidates {
+            if !files.contains(&file.as_str()) {
+                files.push(file);
+            }
+        }
+
+        files
+    }
+
+    pub fn has_characteristic(&self, characteristic: &str) -> bool {
+        self.beatmap_sets
+            .iter()
+            .any(|set| set.characteristic == characteristic)
+    }
+
+    pub fn has_difficulty(&self, difficulty: &str) -> bool {
+        self.beatmaps()
+            .any(|(_, beatmap)| beatmap.difficulty == difficulty)
+    }
+
+    pub fn max_note_jump_speed(&self) -> f32 {
+        self.beatmaps()
+            .map(|(_, beatmap)| beatmap.note_jump_speed)
+            .fold(0.0, f32::max)
+    }
+
+    /// Work-in-progress levels from a PC install's `CustomWIPLevels`.
+    pub fn is_wip(&self) -> bool {
+        self.folder
+            .as_deref()
+            .is_some_and(|folder| folder.starts_with(WIP_LEVEL_PREFIX))
+    }
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -38,6 +139,7 @@ pub struct Playlist {
     #[serde(rename = "playlistTitle")]
     pub title: String,
     #[serde(rename = "playlistDescription")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub songs: Vec<Song>,
     #[serde(skip)]
@@ -46,10 +148,20 @@ pub struct Playlist {
     pub just_created: bool,
     #[serde(skip)]
     pub file_name: String,
+    /// File the playlist was read from before it was renamed, removed once the renamed file is saved.
+    #[serde(skip)]
+    pub previous_file_name: Option<String>,
+    /// File content as last read from or written to the device, to notice changes made by others.
+    #[serde(skip)]
+    pub disk_content: Option<String>,
     #[serde(rename = "imageString")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<String>,
+    /// The cover was read from `image`, as BeatSaver names it, and is written back there.
+    #[serde(skip)]
+    pub image_key_is_image: bool,
     #[serde(rename = "playlistAuthor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
     #[serde(rename = "syncURL")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -57,229 +169,582 @@ pub struct Playlist {
     #[serde(rename = "customData")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub custom_data: Option<HashMap<String, serde_json::Value>>,
+    /// Keys written by other tools, kept so re-saving doesn't drop them.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Playlist {
+    pub fn new(title: &str) -> Self {
+        Playlist {
+            file_name: format!("{}.json", file_name_safe(title)),
+            changed: true,
+            just_created: true,
+            songs: Vec::new(),
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Default)]
 pub struct Song {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub hash: String,
-    #[serde(alias = "songName")]
+    #[serde(rename = "songName", alias = "name")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(rename = "levelid")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level_id: Option<String>,
+    #[serde(rename = "levelAuthorName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level_author: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub difficulties: Vec<SongDifficulty>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A chart highlighted by PlaylistManager when the song is picked from the playlist.
+#[derive(Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct SongDifficulty {
+    pub characteristic: String,
     pub name: String,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-pub fn get_device_folder() -> String {
-    let device_folder = match fs::read_dir(BASE_PATH) {
-        Ok(read_dir) => {
-            let oculus_dir = read_dir.flatten().find(|dir_entry| {
-                return dir_entry
-                    .file_name()
-                    .to_str()
-                    .unwrap_or("none")
-                    .to_lowercase()
-                    .contains("quest");
+impl Song {
+    pub fn from_level(level: &CustomLevel) -> Self {
+        let hash = level
+            .hash
+            .as_ref()
+            .unwrap_or(&"Unknown".to_string())
+            .to_string();
+
+        Song {
+            name: level.song_name.to_string(),
+            level_id: Some(format!("{}{}", CUSTOM_LEVEL_PREFIX, hash)),
+            level_author: Some(level.level_author.to_string()),
+            hash,
+            ..Default::default()
+        }
+    }
+
+    pub fn targets(&self, characteristic: &str, difficulty: &str) -> bool {
+        self.difficulties.iter().any(|song_difficulty| {
+            song_difficulty
+                .characteristic
+                .eq_ignore_ascii_case(characteristic)
+                && song_difficulty.name.eq_ignore_ascii_case(difficulty)
+        })
+    }
+
+    pub fn toggle_difficulty(&mut self, characteristic: &str, difficulty: &str) {
+        if self.targets(characteristic, difficulty) {
+            self.difficulties.retain(|song_difficulty| {
+                !(song_difficulty
+                    .characteristic
+                    .eq_ignore_ascii_case(characteristic)
+                    && song_difficulty.name.eq_ignore_ascii_case(difficulty))
+            });
+        } else {
+            self.difficulties.push(SongDifficulty {
+                characteristic: characteristic.to_string(),
+                name: difficulty.to_string(),
+                ..Default::default()
             });
+        }
+    }
+}
+
+/// Level as stored in the cache, with what is needed to tell whether it changed.
+#[derive(Deserialize, Serialize, Clone)]
+struct CachedLevel {
+    folder: String,
+    info_dat: FileStamp,
+    folder_modified: u128,
+    level: CustomLevel,
+}
 
-            let oculus_dir = match oculus_dir {
-                Some(dir) => dir.file_name().to_str().unwrap_or("none").to_owned(),
-                None => "none".to_string(),
-            };
+/// Loads levels, re-reading only folders that are new or whose Info.dat changed
+/// since they were cached. Folders that disappeared are dropped from the cache.
+pub fn get_custom_levels(
+    backend: &dyn LibraryBackend,
+    report: &mut LoadReport,
+) -> Result<Vec<CustomLevel>, LibraryError> {
+    load_custom_levels(backend, report, true)
+}
 
-            oculus_dir
+/// Reads every level folder again, ignoring the cache, and rewrites the cache.
+pub fn rescan_custom_levels(
+    backend: &dyn LibraryBackend,
+    report: &mut LoadReport,
+) -> Result<Vec<CustomLevel>, LibraryError> {
+    load_custom_levels(backend, report, false)
+}
+
+fn load_custom_levels(
+    backend: &dyn LibraryBackend,
+    report: &mut LoadReport,
+    use_cache: bool,
+) -> Result<Vec<CustomLevel>, LibraryError> {
+    let mut levels = Vec::<CustomLevel>::new();
+    scan_custom_levels(backend, report, use_cache, &mut |level, _, _| {
+        levels.extend(level);
+        true
+    })?;
+
+    Ok(levels)
+}
+
+/// Walks the level folders and hands every level to `on_progress` as soon as it is
+/// known, together with the number of folders done and the total. Skipped folders
+/// are reported with `None`. Returning `false` from `on_progress` stops the scan;
+/// what was read so far is still cached.
+pub fn scan_custom_levels(
+    backend: &dyn LibraryBackend,
+    report: &mut LoadReport,
+    use_cache: bool,
+    on_progress: &mut dyn FnMut(Option<CustomLevel>, usize, usize) -> bool,
+) -> Result<(), LibraryError> {
+    let cached = if use_cache {
+        read_from_cache(backend)
+    } else {
+        Ok(None)
+    };
+    let mut cached_levels: HashMap<String, CachedLevel> = match cached {
+        Ok(cached_levels) => cached_levels
+            .unwrap_or_default()
+            .into_iter()
+            .map(|cached| (cached.folder.to_string(), cached))
+            .collect(),
+        Err(err) => {
+            report.errors.push(err);
+            HashMap::new()
+        }
+    };
+
+    let folders = match backend.list_levels() {
+        Ok(folders) => folders,
+        Err(err) if !cached_levels.is_empty() => {
+            report.errors.push(LibraryError::SongsFolder(err));
+            let total = cached_levels.len();
+            for (done, cached) in cached_levels.into_values().enumerate() {
+                if !on_progress(Some(into_level(cached)), done + 1, total) {
+                    break;
+                }
+            }
+            return Ok(());
         }
-        Err(_) => "none".to_string(),
+        Err(err) => return Err(LibraryError::SongsFolder(err)),
     };
 
-    device_folder
-}
-
-pub fn get_custom_levels() -> Vec<CustomLevel> {
-    let oculus_folder = get_device_folder();
-
-    match read_from_cache::<CustomLevel>("custom_levels.json") {
-        Some(cached_levels) => match count_custom_levels_on_device(&oculus_folder) {
-            Some(number_of_levels_on_device) => {
-                if number_of_levels_on_device != cached_levels.len() {
-                    println!(
-                        "There are {} songs on device, but {} cached, invalidating cahce.",
-                        number_of_levels_on_device,
-                        cached_levels.len()
-                    );
-                    let levels_from_device = read_custom_levels_from_device(&oculus_folder);
-                    cache(&levels_from_device, "custom_levels.json");
-                    levels_from_device
-                } else {
-                    cached_levels
+    let total = folders.len();
+    let mut changed = !use_cache;
+    let mut reached = None;
+    let mut levels = Vec::<CachedLevel>::new();
+
+    for (done, folder) in folders.iter().enumerate() {
+        let level = match backend.info_dat_stamp(folder) {
+            Ok(stamp) => match cached_levels.remove(folder) {
+                Some(cached) if cached.info_dat == stamp => Some(cached),
+                _ => {
+                    changed = true;
+                    read_custom_level(backend, folder.to_string(), stamp, report)
                 }
+            },
+            Err(source) => {
+                report.skipped_levels.push(LibraryError::ReadInfoDat {
+                    folder: folder.to_string(),
+                    source,
+                });
+                None
             }
-            None => cached_levels,
-        },
-        None => {
-            let levels_from_device = read_custom_levels_from_device(&oculus_folder);
-            cache(&levels_from_device, "custom_levels.json");
-            levels_from_device
+        };
+
+        let converted = level.as_ref().map(|level| into_level(level.clone()));
+        levels.extend(level);
+
+        if !on_progress(converted, done + 1, total) {
+            reached = Some(&folders[..=done]);
+            break;
+        }
+    }
+
+    if let Some(reached) = reached {
+        // Folders not reached yet keep their old entries, the next scan checks them.
+        // A rescan didn't read the cache, so it is read now to find those entries.
+        if !use_cache {
+            cached_levels = read_from_cache(backend)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|cached| !reached.contains(&cached.folder))
+                .map(|cached| (cached.folder.to_string(), cached))
+                .collect();
+        }
+        levels.extend(cached_levels.into_values());
+    } else if !cached_levels.is_empty() {
+        // Whatever is left in the cache belongs to folders that were removed.
+        changed = true;
+    }
+
+    if changed {
+        if let Err(err) = cache(backend, &levels) {
+            report.errors.push(err);
         }
     }
+
+    Ok(())
+}
+
+pub fn get_playlists(
+    backend: &dyn LibraryBackend,
+    report: &mut LoadReport,
+) -> Result<Vec<Playlist>, LibraryError> {
+    read_playlists(backend, report)
 }
 
-pub fn get_playlists() -> Vec<Playlist> {
-    read_playlists_from_device(&get_device_folder())
+/// Reads a single playlist file, `None` when it doesn't exist (anymore).
+pub fn get_playlist(
+    backend: &dyn LibraryBackend,
+    file_name: &str,
+) -> Result<Option<Playlist>, LibraryError> {
+    match read_playlist(backend, file_name.to_string()) {
+        Ok(playlist) => Ok(Some(playlist)),
+        Err(LibraryError::ReadPlaylist { source, .. })
+            if source.kind() == io::ErrorKind::NotFound =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
 }
 
-pub fn save_modified_playlists(playlists: &[Playlist]) {
-    let count = playlists.iter().filter(|p| p.changed).count();
-    println!("Trying to save {} playlists.", count);
-    //let path = format!("{}/{}{}", BASE_PATH, get_device_folder(), PLAYLISTS_PATH);
-    let path = "";
-    playlists
+/// Reads a single level folder without touching the cache, `None` when the folder
+/// has no Info.dat (anymore) or couldn't be read; the reason goes into `report`.
+pub fn get_custom_level(
+    backend: &dyn LibraryBackend,
+    folder: &str,
+    report: &mut LoadReport,
+) -> Option<CustomLevel> {
+    match backend.info_dat_stamp(folder) {
+        Ok(stamp) => read_custom_level(backend, folder.to_string(), stamp, report).map(into_level),
+        Err(source) if source.kind() == io::ErrorKind::NotFound => None,
+        Err(source) => {
+            report.skipped_levels.push(LibraryError::ReadInfoDat {
+                folder: folder.to_string(),
+                source,
+            });
+            None
+        }
+    }
+}
+
+pub struct SaveOutcome {
+    pub title: String,
+    pub file_name: String,
+    /// The playlist's file was removed instead of written.
+    pub deleted: bool,
+    pub result: Result<(), LibraryError>,
+}
+
+/// Removes the files of the `deleted` playlists and writes every changed one,
+/// after backing up the versions being removed or replaced. Marks the written
+/// playlists as unchanged and keeps only the deletions that failed in `deleted`.
+/// Fails as a whole only when the backup can't be made.
+pub fn save_modified_playlists(
+    backend: &dyn LibraryBackend,
+    playlists: &mut [Playlist],
+    deleted: &mut Vec<Playlist>,
+) -> Result<Vec<SaveOutcome>, LibraryError> {
+    let changed = playlists.iter().filter(|playlist| playlist.changed);
+    let file_names: Vec<String> = deleted
         .iter()
-        .filter(|playlist| playlist.changed)
-        .map(|playlist| {
-            (
-                serde_json::to_string(playlist).unwrap_or_else(|_| " ".to_string()),
-                &playlist.file_name,
-            )
-        })
-        .for_each(|(serialized_playlist, file_name)| {
-            // match File::create(format!("{}/{}", path, file_name)) {
-            match File::create(file_name) {
-                Ok(mut file) => match file.write_all(serialized_playlist.as_bytes()) {
-                    Ok(_) => println!("Playlist saved to {}", file_name),
-                    Err(_) => println!("Can't save playlist to {}", file_name),
-                },
-                Err(_) => println!("Can't create playlist file."),
+        .chain(changed.clone())
+        .map(|playlist| playlist.file_name.to_string())
+        .chain(changed.filter_map(|playlist| playlist.previous_file_name.clone()))
+        .collect();
+
+    backup::create_snapshot(backend, &file_names).map_err(LibraryError::Backup)?;
+
+    // Deletes first, so a new playlist reusing a deleted one's file name survives.
+    let mut outcomes = Vec::new();
+    deleted.retain(|playlist| {
+        let result = match backend.delete_playlist(&playlist.file_name) {
+            Err(source) if source.kind() != io::ErrorKind::NotFound => {
+                Err(LibraryError::DeletePlaylist {
+                    file_name: playlist.file_name.to_string(),
+                    source,
+                })
             }
+            _ => Ok(()),
+        };
+        let failed = result.is_err();
+
+        outcomes.push(SaveOutcome {
+            title: playlist.title.to_string(),
+            file_name: playlist.file_name.to_string(),
+            deleted: true,
+            result,
         });
+        failed
+    });
+
+    outcomes.extend(
+        playlists
+            .iter_mut()
+            .filter(|playlist| playlist.changed)
+            .map(|playlist| {
+                let result = encode_playlist(playlist).and_then(|(bytes, serialized_playlist)| {
+                    backend
+                        .write_playlist(&playlist.file_name, &bytes)
+                        .map_err(|source| LibraryError::WritePlaylist {
+                            file_name: playlist.file_name.to_string(),
+                            source,
+                        })?;
+                    playlist.changed = false;
+                    playlist.just_created = false;
+                    playlist.disk_content = Some(serialized_playlist);
+
+                    // The renamed playlist is safely written, the old file can go.
+                    match playlist
+                        .previous_file_name
+                        .take()
+                        .map(|previous| (backend.delete_playlist(&previous), previous))
+                    {
+                        Some((Err(source), file_name))
+                            if source.kind() != io::ErrorKind::NotFound =>
+                        {
+                            Err(LibraryError::DeletePlaylist { file_name, source })
+                        }
+                        _ => Ok(()),
+                    }
+                });
+
+                SaveOutcome {
+                    title: playlist.title.to_string(),
+                    file_name: playlist.file_name.to_string(),
+                    deleted: false,
+                    result,
+                }
+            }),
+    );
+
+    Ok(outcomes)
+}
+
+/// Replaces the characters file systems don't allow in file names.
+pub fn file_name_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
 }
 
 pub fn is_playlist_contains_song(playlist: &Playlist, song: Song) -> bool {
     playlist
         .songs
         .iter()
-        .any(|playlist_song| playlist_song.hash == song.hash)
+        .any(|playlist_song| playlist_song.hash.eq_ignore_ascii_case(&song.hash))
 }
 
-fn read_playlists_from_device(oculus_folder_name: &str) -> Vec<Playlist> {
-    let path = format!("{}/{}{}", BASE_PATH, oculus_folder_name, PLAYLISTS_PATH);
-    let mut playlists = Vec::<Playlist>::new();
+/// Computes the BeatSaver hash: SHA-1 over Info.dat followed by every
+/// file it references (see `CustomLevel::hashed_files`), as uppercase hex.
+pub fn compute_level_hash(
+    backend: &dyn LibraryBackend,
+    folder: &str,
+    info_dat: &str,
+    level: &CustomLevel,
+) -> Result<String, LibraryError> {
+    let mut hasher = sha1_smol::Sha1::new();
+    hasher.update(info_dat.as_bytes());
 
-    match fs::read_dir(&path) {
-        Ok(read_dir) => {
-            for dir_entry in read_dir.flatten() {
-                if let Ok(string) = fs::read_to_string(dir_entry.path()) {
-                    if let Ok(mut playlist) = serde_json::from_str::<Playlist>(&string) {
-                        playlist.changed = false;
-                        playlist.file_name = dir_entry
-                            .file_name()
-                            .to_str()
-                            .unwrap_or("default_playlist_name.json")
-                            .to_string();
-                        playlists.push(playlist);
-                    } else {
-                        println!(
-                            "Can't deserialize: {}.",
-                            dir_entry.file_name().to_str().unwrap_or("Unknown")
-                        );
-                    }
-                }
-            }
-        }
-        Err(_) => println!("Can't access {}", &path),
+    for file_name in level.hashed_files() {
+        let content = backend
+            .read_level_file(folder, file_name)
+            .map_err(|source| LibraryError::ReadLevelFile {
+                folder: folder.to_string(),
+                file_name: file_name.to_string(),
+                source,
+            })?;
+        hasher.update(&content);
     }
 
-    playlists
+    Ok(hasher.digest().to_string().to_uppercase())
 }
 
-fn read_custom_levels_from_device(oculus_folder_name: &str) -> Vec<CustomLevel> {
-    let mut custom_levels = Vec::<CustomLevel>::new();
-    let path = format!("{}/{}{}", BASE_PATH, oculus_folder_name, SONGS_PATH);
-
-    match fs::read_dir(format!(
-        "{}/{}{}",
-        BASE_PATH, oculus_folder_name, SONGS_PATH
-    )) {
-        Ok(read_dir) => read_dir.flatten().for_each(|dir_entry| {
-            let hash = dir_entry
-                .file_name()
-                .to_str()
-                .unwrap_or("missing_hash")
-                .to_owned();
+fn read_playlists(
+    backend: &dyn LibraryBackend,
+    report: &mut LoadReport,
+) -> Result<Vec<Playlist>, LibraryError> {
+    let mut playlists = Vec::<Playlist>::new();
 
-            let modified = match dir_entry.metadata() {
-                Ok(metadata) => match metadata.modified() {
-                    Ok(time) => time,
-                    Err(_) => SystemTime::now(),
-                },
-                Err(_) => SystemTime::now(),
-            };
-
-            let modified = modified.elapsed().unwrap_or(Duration::ZERO).as_millis();
-
-            match fs::read_to_string(format!("{}/{}/Info.dat", path, hash))
-                .or_else(|_| fs::read_to_string(format!("{}/{}/info.dat", path, hash)))
-            {
-                Ok(info_dat) => {
-                    println!("Reading level: {}, number: {}", hash, custom_levels.len());
-                    match serde_json::from_str::<CustomLevel>(&info_dat) {
-                        Ok(mut level) => {
-                            level.hash = Some(hash);
-                            level.modified = Some(modified);
-                            custom_levels.push(level);
-                        }
-                        Err(_) => println!("Can't deserialize info.dat in the folder: {}", hash),
-                    }
-                }
-                Err(err) => {
-                    println!("Can't read info.dat from folder with name: {}", hash);
-                    match err.kind() {
-                        std::io::ErrorKind::NotFound => println!("File not found."),
-                        std::io::ErrorKind::PermissionDenied => println!("Permission denied."),
-                        std::io::ErrorKind::Interrupted => println!("Interrupted."),
-                        std::io::ErrorKind::InvalidInput => println!("Invalid input."),
-                        std::io::ErrorKind::AlreadyExists => println!("Already exists."),
-                        _ => println!("Unknown error."),
-                    }
-                }
-            }
-        }),
-        Err(_) => println!(
-            "Can't open CustomLevels folder on the {} device.",
-            oculus_folder_name
-        ),
+    for file_name in backend
+        .list_playlists()
+        .map_err(LibraryError::PlaylistsFolder)?
+        .into_iter()
+        .filter(|file_name| PlaylistFormat::from_file_name(file_name).is_some())
+    {
+        match read_playlist(backend, file_name) {
+            Ok(playlist) => playlists.push(playlist),
+            Err(err) => report.skipped_playlists.push(err),
+        }
     }
-    custom_levels
+
+    Ok(playlists)
 }
 
-fn cache<T: Serialize>(entities: &[T], file_name: &str) {
-    let serialized = serde_json::to_string(entities).unwrap_or_else(|_| "[]".to_string());
-    println!("Attempting to serialize entities to {}", file_name);
+fn read_playlist(
+    backend: &dyn LibraryBackend,
+    file_name: String,
+) -> Result<Playlist, LibraryError> {
+    let content = match backend.read_playlist(&file_name) {
+        Ok(content) => content,
+        Err(source) => return Err(LibraryError::ReadPlaylist { file_name, source }),
+    };
 
-    match File::create(file_name) {
-        Ok(mut file) => match file.write_all(serialized.as_bytes()) {
-            Ok(_) => println!("Entities cached to {}", file_name),
-            Err(_) => println!("Can't write entities to {}", file_name),
-        },
-        Err(_) => println!("Can't create cache file."),
-    }
+    let (mut playlist, content) = decode_playlist(&file_name, &content)?;
+    playlist.changed = false;
+    playlist.file_name = file_name;
+    playlist.disk_content = Some(content);
+    Ok(playlist)
 }
 
-fn read_from_cache<T: DeserializeOwned>(file_name: &str) -> Option<Vec<T>> {
-    match fs::read_to_string(file_name) {
-        Ok(string) => {
-            let entities =
-                serde_json::from_str::<Vec<T>>(string.as_str()).unwrap_or_else(|_| Vec::<T>::new());
-            println!("Entities retrived from cache.");
-            Some(entities)
+fn read_custom_level(
+    backend: &dyn LibraryBackend,
+    folder: String,
+    info_dat_stamp: FileStamp,
+    report: &mut LoadReport,
+) -> Option<CachedLevel> {
+    let folder_modified = backend
+        .level_modified(&folder)
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis())
+        .unwrap_or(info_dat_stamp.modified);
+
+    let info_dat = match backend.read_info_dat(&folder) {
+        Ok(info_dat) => info_dat,
+        Err(source) => {
+            report
+                .skipped_levels
+                .push(LibraryError::ReadInfoDat { folder, source });
+            return None;
         }
-        Err(_) => {
-            println!("Can't read cached entities.");
+    };
+
+    match parse_info_dat(&info_dat) {
+        Ok(mut level) => {
+            level.hash = Some(
+                match compute_level_hash(backend, &folder, &info_dat, &level) {
+                    Ok(hash) => hash,
+                    Err(err) => {
+                        report.errors.push(err);
+                        folder.to_string()
+                    }
+                },
+            );
+            level.folder = Some(folder.to_string());
+
+            Some(CachedLevel {
+                folder,
+                info_dat: info_dat_stamp,
+                folder_modified,
+                level,
+            })
+        }
+        Err(source) => {
+            report
+                .skipped_levels
+                .push(LibraryError::ParseInfoDat { folder, source });
             None
         }
     }
 }
 
-fn count_custom_levels_on_device(oculus_folder_name: &str) -> Option<usize> {
-    let path = format!("{}/{}{}", BASE_PATH, oculus_folder_name, SONGS_PATH);
-    match fs::read_dir(path) {
-        Ok(dir) => Some(dir.count()),
-        Err(_) => None,
+/// `CustomLevel::modified` is the time since the folder was modified, so it is
+/// worked out on every load rather than stored.
+fn into_level(cached: CachedLevel) -> CustomLevel {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    let mut level = cached.level;
+    level.modified = Some(now.saturating_sub(cached.folder_modified));
+    level
+}
+
+/// Bump whenever `CachedLevel` or `CustomLevel` change shape.
+const CACHE_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct CacheHeader {
+    schema_version: u32,
+    device: String,
+}
+
+#[derive(Serialize)]
+struct LevelCache<'a> {
+    schema_version: u32,
+    device: String,
+    levels: &'a [CachedLevel],
+}
+
+#[derive(Deserialize)]
+struct LevelCacheContent {
+    levels: Vec<CachedLevel>,
+}
+
+fn cache_path(backend: &dyn LibraryBackend) -> PathBuf {
+    paths::cache_dir().join(format!(
+        "custom_levels-{}.json",
+        paths::device_key(&backend.id())
+    ))
+}
+
+fn cache(backend: &dyn LibraryBackend, levels: &[CachedLevel]) -> Result<(), LibraryError> {
+    let path = cache_path(backend);
+    let serialized = serde_json::to_string(&LevelCache {
+        schema_version: CACHE_SCHEMA_VERSION,
+        device: backend.id(),
+        levels,
+    })
+    .map_err(LibraryError::SerializeCache)?;
+
+    path.parent()
+        .map(fs::create_dir_all)
+        .unwrap_or(Ok(()))
+        .and_then(|_| File::create(&path))
+        .and_then(|mut file| file.write_all(serialized.as_bytes()))
+        .map_err(LibraryError::WriteCache)
+}
+
+/// Returns `None` when there is no usable cache yet. A cache written by another
+/// schema version or for another device is discarded, the scan rebuilds it.
+fn read_from_cache(backend: &dyn LibraryBackend) -> Result<Option<Vec<CachedLevel>>, LibraryError> {
+    let content = match fs::read_to_string(cache_path(backend)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(LibraryError::ReadCache(err)),
+    };
+
+    let header = serde_json::from_str::<CacheHeader>(&content).map_err(LibraryError::ParseCache)?;
+    if header.schema_version != CACHE_SCHEMA_VERSION || header.device != backend.id() {
+        return Ok(None);
     }
+
+    serde_json::from_str::<LevelCacheContent>(&content)
+        .map(|cache| Some(cache.levels))
+        .map_err(LibraryError::ParseCache)
 }
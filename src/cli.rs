@@ -0,0 +1,434 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::json;
+
+use playlist_creator::{
+    backend::{Layout, LibraryBackend, LocalBackend},
+    bmbf_config::read_bmbf_playlists,
+    bmbf_utils::*,
+    cover_mosaic::generate_cover,
+    editing::{create_playlist, import_playlists, playlist_levels, rename_playlist},
+    error::LoadReport,
+    playlist_format::decode_playlist,
+    settings::{load_settings, settings_path},
+};
+
+const USAGE: &str =
+    "Usage: playlist_creator [--root <dir> [--layout quest|pc|bmbf] | --library <name>] [--json]
+                        <command> [args]
+
+Commands:
+  list-levels                      List custom levels
+  list-playlists                   List playlists
+  create <title>                   Create an empty playlist
+  add <playlist> <hash>...         Add levels (by hash or folder name) to a playlist
+  remove <playlist> <hash>...      Remove levels from a playlist
+  rename <playlist> <title>        Retitle a playlist and rename its file
+  generate-cover <playlist>        Make a cover from the covers of the playlist's levels
+  delete <playlist>                Delete a playlist, keeping a backup copy
+  export <playlist> [file]         Write a playlist as JSON to a file or stdout
+  save <file>...                   Copy .json, .bplist or .blist playlists into the playlists folder
+  import-bmbf <config.json>        Write the playlists kept in a BMBF config as files
+
+Without --root the library named by --library, or else the one opened last, is
+read from the settings file. Playlists are looked up by title or file name.
+Without a command the GUI is started.";
+
+struct Options {
+    root: Option<PathBuf>,
+    layout: Layout,
+    library: Option<String>,
+    json: bool,
+    command: String,
+    args: Vec<String>,
+}
+
+/// Returns true when the arguments ask for a command instead of the GUI.
+pub fn is_cli_invocation(args: &[String]) -> bool {
+    args.len() > 1
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let backend = match &options.root {
+        Some(root) => LocalBackend::from_layout(root, options.layout),
+        None => settings_backend(&options)?,
+    };
+
+    match options.command.as_str() {
+        "list-levels" => list_levels(&backend, &options),
+        "list-playlists" => list_playlists(&backend, &options),
+        "create" => create(&backend, &options),
+        "add" => add(&backend, &options),
+        "remove" => remove(&backend, &options),
+        "rename" => rename(&backend, &options),
+        "generate-cover" => generate_playlist_cover(&backend, &options),
+        "delete" => delete(&backend, &options),
+        "export" => export(&backend, &options),
+        "save" => save(&backend, &options),
+        "import-bmbf" => import_bmbf(&backend, &options),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut root = None;
+    let mut layout = Layout::Quest;
+    let mut library = None;
+    let mut json = false;
+    let mut positional = Vec::<String>::new();
+    let mut args = args.iter().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--root" => match args.next() {
+                Some(path) => root = Some(PathBuf::from(path)),
+                None => return Err("--root needs a directory".to_string()),
+            },
+            "--layout" => match args.next().map(String::as_str) {
+                Some("quest") => layout = Layout::Quest,
+                Some("pc") => layout = Layout::Pc,
+                Some("bmbf") => layout = Layout::Bmbf,
+                _ => return Err("--layout needs quest, pc or bmbf".to_string()),
+            },
+            "--library" => match args.next() {
+                Some(name) => library = Some(name.to_string()),
+                None => return Err("--library needs a name".to_string()),
+            },
+            _ => positional.push(arg.to_string()),
+        }
+    }
+
+    if positional.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    Ok(Options {
+        root,
+        layout,
+        library,
+        json,
+        command: positional.remove(0),
+        args: positional,
+    })
+}
+
+fn settings_backend(options: &Options) -> Result<LocalBackend, String> {
+    let settings = load_settings().map_err(|err| err.to_string())?;
+
+    let root = match &options.library {
+        Some(name) => settings
+            .find_root(name)
+            .ok_or_else(|| format!("No library named {} in {}", name, settings_path().display()))?,
+        None => settings.default_root().ok_or_else(|| {
+            format!(
+                "No device found. Connect one, use --root or add a library to {}",
+                settings_path().display()
+            )
+        })?,
+    };
+
+    root.backend()
+        .ok_or_else(|| format!("No device found for {}", root.name))
+}
+
+fn list_levels(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let levels = load_levels(backend)?;
+
+    if options.json {
+        print_json(&levels)
+    } else {
+        for level in levels.iter() {
+            println!(
+                "{}\t{}\t{}\t{}",
+                level.hash.as_deref().unwrap_or("Unknown"),
+                level.beats_per_minute as i32,
+                level.song_name,
+                level.song_author
+            );
+        }
+        Ok(())
+    }
+}
+
+fn list_playlists(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let playlists = load_playlists(backend)?;
+
+    if options.json {
+        let summaries: Vec<serde_json::Value> = playlists
+            .iter()
+            .map(|playlist| {
+                json!({
+                    "fileName": playlist.file_name,
+                    "title": playlist.title,
+                    "songs": playlist.songs.iter().map(|song| &song.hash).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        print_json(&summaries)
+    } else {
+        for playlist in playlists.iter() {
+            println!(
+                "{}\t{}\t{} songs",
+                playlist.file_name,
+                playlist.title,
+                playlist.songs.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+fn create(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let title = options.args.first().ok_or("create needs a title")?;
+    let mut playlists = load_playlists(backend)?;
+    create_playlist(&mut playlists, title)?;
+
+    write_playlists(backend, options, &mut playlists, Vec::new())
+}
+
+fn add(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let (playlist_name, hashes) = options
+        .args
+        .split_first()
+        .ok_or("add needs a playlist and at least one hash")?;
+    let levels = load_levels(backend)?;
+    let mut playlists = load_playlists(backend)?;
+    let playlist = find_playlist(&mut playlists, playlist_name)?;
+
+    for hash in hashes {
+        let level = levels
+            .iter()
+            .find(|level| {
+                level
+                    .hash
+                    .as_ref()
+                    .map(|level_hash| level_hash.eq_ignore_ascii_case(hash))
+                    .unwrap_or(false)
+                    || level.folder.as_ref() == Some(hash)
+            })
+            .ok_or_else(|| format!("No level with hash {}", hash))?;
+        playlist.add_level(level);
+    }
+
+    write_playlists(backend, options, &mut playlists, Vec::new())
+}
+
+fn remove(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let (playlist_name, hashes) = options
+        .args
+        .split_first()
+        .ok_or("remove needs a playlist and at least one hash")?;
+    let mut playlists = load_playlists(backend)?;
+    let playlist = find_playlist(&mut playlists, playlist_name)?;
+    let count = playlist.songs.len();
+
+    playlist.songs.retain(|song| {
+        !hashes
+            .iter()
+            .any(|hash| song.hash.eq_ignore_ascii_case(hash))
+    });
+    playlist.changed = playlist.songs.len() != count;
+
+    write_playlists(backend, options, &mut playlists, Vec::new())
+}
+
+fn rename(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let (playlist_name, title) = match options.args.as_slice() {
+        [playlist_name, title] => (playlist_name, title),
+        _ => return Err("rename needs a playlist and a new title".to_string()),
+    };
+    let mut playlists = load_playlists(backend)?;
+    let index = playlists
+        .iter()
+        .position(|playlist| {
+            &playlist.title == playlist_name || &playlist.file_name == playlist_name
+        })
+        .ok_or_else(|| format!("No playlist named {}", playlist_name))?;
+    rename_playlist(&mut playlists, index, title)?;
+
+    write_playlists(backend, options, &mut playlists, Vec::new())
+}
+
+fn generate_playlist_cover(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let playlist_name = options
+        .args
+        .first()
+        .ok_or("generate-cover needs a playlist")?;
+    let levels = load_levels(backend)?;
+    let mut playlists = load_playlists(backend)?;
+    let playlist = find_playlist(&mut playlists, playlist_name)?;
+
+    let image = generate_cover(
+        backend,
+        &playlist_levels(playlist, &levels),
+        &playlist.title,
+    )
+    .map_err(|err| err.to_string())?;
+    playlist.image = Some(image);
+    playlist.changed = true;
+
+    write_playlists(backend, options, &mut playlists, Vec::new())
+}
+
+fn delete(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let playlist_name = options.args.first().ok_or("delete needs a playlist")?;
+    let mut playlists = load_playlists(backend)?;
+    let index = playlists
+        .iter()
+        .position(|playlist| {
+            &playlist.title == playlist_name || &playlist.file_name == playlist_name
+        })
+        .ok_or_else(|| format!("No playlist named {}", playlist_name))?;
+    let deleted = playlists.remove(index);
+
+    write_playlists(backend, options, &mut playlists, vec![deleted])
+}
+
+fn export(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let playlist_name = options.args.first().ok_or("export needs a playlist")?;
+    let mut playlists = load_playlists(backend)?;
+    let playlist = find_playlist(&mut playlists, playlist_name)?;
+    let serialized = serde_json::to_string_pretty(playlist).map_err(|err| err.to_string())?;
+
+    match options.args.get(1) {
+        Some(file) => fs::write(file, serialized)
+            .map_err(|err| format!("Can't write playlist to {}: {}", file, err)),
+        None => {
+            println!("{}", serialized);
+            Ok(())
+        }
+    }
+}
+
+fn save(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    if options.args.is_empty() {
+        return Err("save needs at least one playlist file".to_string());
+    }
+
+    let mut playlists = options
+        .args
+        .iter()
+        .map(|file| {
+            let path = PathBuf::from(file);
+            let content = fs::read(&path).map_err(|err| format!("Can't read {}: {}", file, err))?;
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("default_playlist_name.json")
+                .to_string();
+            let (mut playlist, _) =
+                decode_playlist(&file_name, &content).map_err(|err| err.to_string())?;
+            playlist.file_name = file_name;
+            playlist.changed = true;
+            Ok(playlist)
+        })
+        .collect::<Result<Vec<Playlist>, String>>()?;
+
+    write_playlists(backend, options, &mut playlists, Vec::new())
+}
+
+fn import_bmbf(backend: &dyn LibraryBackend, options: &Options) -> Result<(), String> {
+    let config = options
+        .args
+        .first()
+        .ok_or("import-bmbf needs a config.json")?;
+    let imported = read_bmbf_playlists(Path::new(config)).map_err(|err| err.to_string())?;
+    let mut playlists = load_playlists(backend)?;
+
+    for title in import_playlists(&mut playlists, imported) {
+        eprintln!(
+            "Skipped {}, a playlist with that name already exists",
+            title
+        );
+    }
+
+    write_playlists(backend, options, &mut playlists, Vec::new())
+}
+
+fn load_levels(backend: &dyn LibraryBackend) -> Result<Vec<CustomLevel>, String> {
+    let mut report = LoadReport::default();
+    let levels = get_custom_levels(backend, &mut report);
+    print_issues(&report);
+    levels.map_err(|err| err.to_string())
+}
+
+fn load_playlists(backend: &dyn LibraryBackend) -> Result<Vec<Playlist>, String> {
+    let mut report = LoadReport::default();
+    let playlists = get_playlists(backend, &mut report);
+    print_issues(&report);
+    playlists.map_err(|err| err.to_string())
+}
+
+fn print_issues(report: &LoadReport) {
+    for issue in report.issues() {
+        eprintln!("{}", issue);
+    }
+}
+
+fn find_playlist<'a>(
+    playlists: &'a mut [Playlist],
+    name: &str,
+) -> Result<&'a mut Playlist, String> {
+    playlists
+        .iter_mut()
+        .find(|playlist| playlist.title == name || playlist.file_name == name)
+        .ok_or_else(|| format!("No playlist named {}", name))
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), String> {
+    let serialized = serde_json::to_string_pretty(value).map_err(|err| err.to_string())?;
+    println!("{}", serialized);
+    Ok(())
+}
+
+fn write_playlists(
+    backend: &dyn LibraryBackend,
+    options: &Options,
+    playlists: &mut [Playlist],
+    mut deleted: Vec<Playlist>,
+) -> Result<(), String> {
+    let outcomes =
+        save_modified_playlists(backend, playlists, &mut deleted).map_err(|err| err.to_string())?;
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| outcome.result.is_err())
+        .count();
+
+    if options.json {
+        let report: Vec<serde_json::Value> = outcomes
+            .iter()
+            .map(|outcome| {
+                json!({
+                    "fileName": outcome.file_name,
+                    "title": outcome.title,
+                    "deleted": outcome.deleted,
+                    "error": outcome.result.as_ref().err().map(|err| err.to_string()),
+                })
+            })
+            .collect();
+        print_json(&report)?;
+    } else {
+        for outcome in outcomes.iter() {
+            match &outcome.result {
+                Ok(_) if outcome.deleted => println!("Deleted {}", outcome.file_name),
+                Ok(_) => println!("Saved {}", outcome.file_name),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+    }
+
+    if failed > 0 {
+        Err(format!("{} playlists couldn't be saved", failed))
+    } else {
+        Ok(())
+    }
+}
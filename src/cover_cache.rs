@@ -0,0 +1,143 @@
+use std::{collections::HashMap, sync::Arc};
+
+use playlist_creator::{
+    backend::LibraryBackend,
+    bmbf_utils::{CustomLevel, Playlist},
+    covers::{CoverLoader, CoverSource},
+};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum CoverKey {
+    Level { folder: String, size: u32 },
+    Playlist { file_name: String, size: u32 },
+}
+
+enum CoverState {
+    Loading,
+    Loaded(egui::TextureHandle),
+    /// Missing, unreadable or not an image; not retried.
+    Missing,
+}
+
+struct CoverEntry {
+    state: CoverState,
+    /// The `imageString` a playlist cover was made from, to notice a new cover.
+    image: Option<String>,
+}
+
+/// Cover textures for levels and playlists, decoded in the background on first use.
+pub struct CoverCache {
+    loader: CoverLoader<CoverKey>,
+    entries: HashMap<CoverKey, CoverEntry>,
+}
+
+impl CoverCache {
+    pub fn new(backend: Arc<dyn LibraryBackend>) -> Self {
+        CoverCache {
+            loader: CoverLoader::start(backend),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// The level's cover scaled to fit `size` pixels, `None` while it loads or when there is none.
+    pub fn level_cover(&mut self, level: &CustomLevel, size: u32) -> Option<&egui::TextureHandle> {
+        let folder = level.folder.as_ref()?;
+        if level.cover_image_filename.is_empty() {
+            return None;
+        }
+
+        let key = CoverKey::Level {
+            folder: folder.to_string(),
+            size,
+        };
+        if !self.entries.contains_key(&key) {
+            self.loader.request(
+                key.clone(),
+                CoverSource::LevelFile {
+                    folder: folder.to_string(),
+                    file_name: level.cover_image_filename.to_string(),
+                },
+                size,
+            );
+            self.entries.insert(
+                key.clone(),
+                CoverEntry {
+                    state: CoverState::Loading,
+                    image: None,
+                },
+            );
+        }
+
+        self.texture(&key)
+    }
+
+    pub fn playlist_cover(
+        &mut self,
+        playlist: &Playlist,
+        size: u32,
+    ) -> Option<&egui::TextureHandle> {
+        let image = playlist.image.as_ref()?;
+
+        let key = CoverKey::Playlist {
+            file_name: playlist.file_name.to_string(),
+            size,
+        };
+        let is_current = self
+            .entries
+            .get(&key)
+            .is_some_and(|entry| entry.image.as_ref() == Some(image));
+        if !is_current {
+            self.loader
+                .request(key.clone(), CoverSource::ImageString(image.clone()), size);
+            self.entries.insert(
+                key.clone(),
+                CoverEntry {
+                    state: CoverState::Loading,
+                    image: Some(image.clone()),
+                },
+            );
+        }
+
+        self.texture(&key)
+    }
+
+    /// Drops the covers of a level folder that changed, so they're read again.
+    pub fn forget_level(&mut self, changed_folder: &str) {
+        self.entries.retain(|key, _| match key {
+            CoverKey::Level { folder, .. } => folder != changed_folder,
+            CoverKey::Playlist { .. } => true,
+        });
+    }
+
+    /// Turns decoded covers into textures. Returns whether some are still loading.
+    pub fn poll(&mut self, ctx: &egui::Context) -> bool {
+        for (key, cover) in self.loader.poll() {
+            let Some(entry) = self.entries.get_mut(&key) else {
+                continue;
+            };
+
+            entry.state = match cover {
+                Ok(cover) => CoverState::Loaded(ctx.load_texture(
+                    "cover",
+                    egui::ColorImage::from_rgba_unmultiplied(
+                        [cover.width, cover.height],
+                        &cover.rgba,
+                    ),
+                    egui::TextureOptions::LINEAR,
+                )),
+                Err(_) => CoverState::Missing,
+            };
+        }
+
+        self.entries
+            .values()
+            .any(|entry| matches!(entry.state, CoverState::Loading))
+    }
+
+    fn texture(&self, key: &CoverKey) -> Option<&egui::TextureHandle> {
+        match &self.entries.get(key)?.state {
+            CoverState::Loaded(texture) => Some(texture),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::io::Cursor;
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{imageops, imageops::FilterType, DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
+
+use crate::{backend::LibraryBackend, bmbf_utils::CustomLevel, error::LibraryError};
+
+const SIZE: u32 = 512;
+const BACKGROUND: Rgba<u8> = Rgba([40, 40, 48, 255]);
+/// Ubuntu Light, under the Ubuntu Font Licence in `assets/UFL.txt`.
+const TITLE_FONT: &[u8] = include_bytes!("../assets/Ubuntu-Light.ttf");
+const TITLE_SIZE: f32 = 72.0;
+const TITLE_MARGIN: f32 = 24.0;
+
+/// Composes a playlist cover from the covers of its levels: a 2x2 mosaic, or
+/// 3x3 once there are nine covers, with the title across the bottom. Levels
+/// whose cover can't be read are left out, covers repeat to fill the grid.
+/// Returns the PNG as base64 for `imageString`.
+pub fn generate_cover(
+    backend: &dyn LibraryBackend,
+    levels: &[CustomLevel],
+    title: &str,
+) -> Result<String, LibraryError> {
+    let covers: Vec<DynamicImage> = levels
+        .iter()
+        .filter(|level| !level.cover_image_filename.is_empty())
+        .filter_map(|level| {
+            let bytes = backend
+                .read_level_file(level.folder.as_ref()?, &level.cover_image_filename)
+                .ok()?;
+            image::load_from_memory(&bytes).ok()
+        })
+        .take(9)
+        .collect();
+
+    let columns = if covers.len() >= 9 { 3 } else { 2 };
+    let tile = SIZE / columns;
+    // A 3x3 grid leaves a few pixels of background at the edges.
+    let offset = (SIZE - tile * columns) / 2;
+    let mut canvas = RgbaImage::from_pixel(SIZE, SIZE, BACKGROUND);
+
+    if !covers.is_empty() {
+        for index in 0..columns * columns {
+            let cover = covers[index as usize % covers.len()]
+                .resize_to_fill(tile, tile, FilterType::Triangle)
+                .into_rgba8();
+            imageops::replace(
+                &mut canvas,
+                &cover,
+                (offset + index % columns * tile) as i64,
+                (offset + index / columns * tile) as i64,
+            );
+        }
+    }
+
+    draw_title(&mut canvas, title)?;
+
+    let mut png = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(canvas)
+        .write_to(&mut png, ImageOutputFormat::Png)
+        .map_err(LibraryError::EncodeCover)?;
+
+    Ok(STANDARD.encode(png.into_inner()))
+}
+
+/// Darkens the bottom quarter and writes the title there, shrunk to fit the width.
+fn draw_title(canvas: &mut RgbaImage, title: &str) -> Result<(), LibraryError> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Ok(());
+    }
+    let font = FontRef::try_from_slice(TITLE_FONT).map_err(LibraryError::CoverFont)?;
+
+    let band_top = SIZE - SIZE / 4;
+    for y in band_top..SIZE {
+        for x in 0..SIZE {
+            let pixel = canvas.get_pixel_mut(x, y);
+            for channel in pixel.0.iter_mut().take(3) {
+                *channel = (*channel as f32 * 0.35) as u8;
+            }
+        }
+    }
+
+    let available = SIZE as f32 - 2.0 * TITLE_MARGIN;
+    let width = text_width(&font, TITLE_SIZE, title);
+    let scale = if width > available {
+        TITLE_SIZE * available / width
+    } else {
+        TITLE_SIZE
+    };
+    let scaled = font.as_scaled(PxScale::from(scale));
+
+    let width = text_width(&font, scale, title);
+    let mut x = (SIZE as f32 - width) / 2.0;
+    let baseline = band_top as f32
+        + (SIZE - band_top) as f32 / 2.0
+        + (scaled.ascent() + scaled.descent()) / 2.0;
+
+    let mut previous = None;
+    for c in title.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            x += scaled.kern(previous, id);
+        }
+        previous = Some(id);
+
+        let glyph = id.with_scale_and_position(scale, point(x, baseline));
+        x += scaled.h_advance(id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|glyph_x, glyph_y, coverage| {
+            let x = bounds.min.x as i64 + glyph_x as i64;
+            let y = bounds.min.y as i64 + glyph_y as i64;
+            if x < 0 || y < 0 || x >= SIZE as i64 || y >= SIZE as i64 {
+                return;
+            }
+
+            let pixel = canvas.get_pixel_mut(x as u32, y as u32);
+            for channel in pixel.0.iter_mut().take(3) {
+                *channel = (*channel as f32 + (255.0 - *channel as f32) * coverage) as u8;
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn text_width(font: &FontRef, scale: f32, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(scale));
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+
+    width
+}
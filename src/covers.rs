@@ -0,0 +1,95 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{backend::LibraryBackend, error::LibraryError, playlist_format::decode_image_string};
+
+/// Where a cover image is read from.
+pub enum CoverSource {
+    /// A playlist's base64 `imageString`.
+    ImageString(String),
+    /// An image file inside a level folder.
+    LevelFile { folder: String, file_name: String },
+}
+
+/// A cover scaled down to fit a square, as unmultiplied RGBA pixels.
+pub struct DecodedCover {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+struct CoverRequest<K> {
+    key: K,
+    source: CoverSource,
+    size: u32,
+}
+
+/// Reads and decodes covers on a worker thread, so large images don't stall the UI.
+/// The requests are answered in order, tagged with the key they were made with.
+pub struct CoverLoader<K> {
+    sender: Sender<CoverRequest<K>>,
+    receiver: Receiver<(K, Result<DecodedCover, LibraryError>)>,
+}
+
+impl<K: Send + 'static> CoverLoader<K> {
+    pub fn start(backend: Arc<dyn LibraryBackend>) -> Self {
+        let (sender, requests) = mpsc::channel::<CoverRequest<K>>();
+        let (results, receiver) = mpsc::channel();
+
+        // Ends once the loader, and with it the sender, is dropped.
+        thread::spawn(move || {
+            for request in requests {
+                let cover = load_cover(backend.as_ref(), request.source, request.size);
+                if results.send((request.key, cover)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        CoverLoader { sender, receiver }
+    }
+
+    /// Queues a cover to be scaled to fit `size` × `size` pixels.
+    pub fn request(&self, key: K, source: CoverSource, size: u32) {
+        let _ = self.sender.send(CoverRequest { key, source, size });
+    }
+
+    /// Covers decoded since the last call.
+    pub fn poll(&self) -> Vec<(K, Result<DecodedCover, LibraryError>)> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+fn load_cover(
+    backend: &dyn LibraryBackend,
+    source: CoverSource,
+    size: u32,
+) -> Result<DecodedCover, LibraryError> {
+    let bytes = match source {
+        CoverSource::ImageString(image) => {
+            decode_image_string(&image).map_err(LibraryError::CoverEncoding)?
+        }
+        CoverSource::LevelFile { folder, file_name } => backend
+            .read_level_file(&folder, &file_name)
+            .map_err(|source| LibraryError::ReadCover {
+                path: format!("{}/{}", folder, file_name),
+                source,
+            })?,
+    };
+
+    let image = image::load_from_memory(&bytes)
+        .map_err(LibraryError::DecodeCover)?
+        .thumbnail(size, size)
+        .into_rgba8();
+
+    Ok(DecodedCover {
+        width: image.width() as usize,
+        height: image.height() as usize,
+        rgba: image.into_raw(),
+    })
+}
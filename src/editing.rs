@@ -0,0 +1,305 @@
+use crate::bmbf_utils::{file_name_safe, is_playlist_contains_song, CustomLevel, Playlist, Song};
+
+impl Playlist {
+    /// Appends the level unless the playlist already has it. Returns whether it was added.
+    pub fn add_level(&mut self, level: &CustomLevel) -> bool {
+        if is_playlist_contains_song(self, Song::from_level(level)) {
+            return false;
+        }
+
+        self.songs.push(Song::from_level(level));
+        self.changed = true;
+        true
+    }
+
+    pub fn remove_song(&mut self, index: usize) -> Option<Song> {
+        if index >= self.songs.len() {
+            return None;
+        }
+
+        self.changed = true;
+        Some(self.songs.remove(index))
+    }
+
+    /// Moves the song at `from` so it ends up at `to`. Returns whether anything moved.
+    pub fn move_song(&mut self, from: usize, to: usize) -> bool {
+        if from == to || from >= self.songs.len() || to >= self.songs.len() {
+            return false;
+        }
+
+        let song = self.songs.remove(from);
+        self.songs.insert(to, song);
+        self.changed = true;
+        true
+    }
+}
+
+/// Adds a new empty playlist and returns its index, or an error when the title
+/// or the file name made from it is taken.
+pub fn create_playlist(playlists: &mut Vec<Playlist>, title: &str) -> Result<usize, String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("Playlist title can't be empty".to_string());
+    }
+
+    let playlist = Playlist::new(title);
+    if is_name_taken(playlists, None, title, &playlist.file_name) {
+        return Err(name_taken_error(title, &playlist.file_name));
+    }
+
+    playlists.push(playlist);
+    Ok(playlists.len() - 1)
+}
+
+/// Adds imported playlists whose title and file name aren't taken yet, and
+/// returns the titles of the ones that were left out.
+pub fn import_playlists(playlists: &mut Vec<Playlist>, imported: Vec<Playlist>) -> Vec<String> {
+    let mut skipped = Vec::new();
+
+    for playlist in imported {
+        let is_taken = playlists.iter().any(|existing| {
+            existing.title == playlist.title || existing.file_name == playlist.file_name
+        });
+
+        if is_taken {
+            skipped.push(playlist.title);
+        } else {
+            playlists.push(playlist);
+        }
+    }
+
+    skipped
+}
+
+/// Changes a playlist's title. Its file is renamed after the title, keeping the
+/// extension, on the next save. Fails when the title or file name is taken.
+pub fn rename_playlist(
+    playlists: &mut [Playlist],
+    index: usize,
+    title: &str,
+) -> Result<(), String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("Playlist title can't be empty".to_string());
+    }
+
+    let Some(playlist) = playlists.get(index) else {
+        return Err("No playlist selected".to_string());
+    };
+    let extension = playlist
+        .file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension)
+        .unwrap_or("json");
+    let file_name = format!("{}.{}", file_name_safe(title), extension);
+
+    if is_name_taken(playlists, Some(index), title, &file_name) {
+        return Err(name_taken_error(title, &file_name));
+    }
+
+    let playlist = &mut playlists[index];
+    if playlist.file_name != file_name {
+        if !playlist.just_created && playlist.previous_file_name.is_none() {
+            playlist.previous_file_name = Some(playlist.file_name.to_string());
+        }
+        if playlist.previous_file_name.as_ref() == Some(&file_name) {
+            playlist.previous_file_name = None;
+        }
+        playlist.file_name = file_name;
+    }
+    playlist.title = title.to_string();
+    playlist.changed = true;
+    Ok(())
+}
+
+/// Whether a playlist other than `except` has the title or, ignoring case as some
+/// file systems do, the file name. The old name of a renamed playlist counts as
+/// taken until the save removes it, or that removal would hit the new file.
+fn is_name_taken(
+    playlists: &[Playlist],
+    except: Option<usize>,
+    title: &str,
+    file_name: &str,
+) -> bool {
+    playlists.iter().enumerate().any(|(index, playlist)| {
+        let has_file_name = |name: &str| name.eq_ignore_ascii_case(file_name);
+        Some(index) != except
+            && (playlist.title == title
+                || has_file_name(&playlist.file_name)
+                || playlist
+                    .previous_file_name
+                    .as_deref()
+                    .is_some_and(has_file_name))
+    })
+}
+
+fn name_taken_error(title: &str, file_name: &str) -> String {
+    format!(
+        "Playlist with the title {} or the file name {} already exists",
+        title, file_name
+    )
+}
+
+pub fn delete_playlist(playlists: &mut Vec<Playlist>, index: usize) -> Option<Playlist> {
+    if index >= playlists.len() {
+        return None;
+    }
+
+    Some(playlists.remove(index))
+}
+
+pub fn find_level_by_hash<'a>(levels: &'a [CustomLevel], hash: &str) -> Option<&'a CustomLevel> {
+    levels.iter().find(|level| {
+        level
+            .hash
+            .as_ref()
+            .map(|level_hash| level_hash.eq_ignore_ascii_case(hash))
+            .unwrap_or(false)
+    })
+}
+
+/// The levels of the playlist's songs that are in the library, in playlist order.
+pub fn playlist_levels(playlist: &Playlist, levels: &[CustomLevel]) -> Vec<CustomLevel> {
+    playlist
+        .songs
+        .iter()
+        .filter_map(|song| find_level_by_hash(levels, &song.hash).cloned())
+        .collect()
+}
+
+pub fn is_level_assigned(level: &CustomLevel, playlists: &[Playlist]) -> bool {
+    playlists.iter().any(|playlist| {
+        playlist.songs.iter().any(|playlist_song| {
+            level.hash.is_some()
+                && playlist_song
+                    .hash
+                    .eq_ignore_ascii_case(level.hash.as_ref().unwrap())
+        })
+    })
+}
+
+/// The playlists that have the level.
+pub fn playlists_containing<'a>(
+    level: &'a CustomLevel,
+    playlists: &'a [Playlist],
+) -> impl Iterator<Item = &'a Playlist> {
+    playlists
+        .iter()
+        .filter(|playlist| is_playlist_contains_song(playlist, Song::from_level(level)))
+}
+
+/// Levels that aren't in any of the playlists.
+pub fn unassigned_levels(levels: &[CustomLevel], playlists: &[Playlist]) -> Vec<CustomLevel> {
+    levels
+        .iter()
+        .filter(|level| !is_level_assigned(level, playlists))
+        .cloned()
+        .collect()
+}
+
+/// A playlist edited here whose file was also changed by someone else.
+pub struct PlaylistConflict {
+    pub file_name: String,
+    pub on_disk: Playlist,
+}
+
+/// Merges playlists freshly read from disk into the ones being edited.
+///
+/// Unchanged playlists are replaced by their disk version, new files are added and
+/// deleted files are dropped. Edited playlists are kept; when their file changed
+/// since it was read, the disk version is returned as a conflict to resolve.
+pub fn merge_reloaded_playlists(
+    playlists: &mut Vec<Playlist>,
+    mut on_disk: Vec<Playlist>,
+) -> Vec<PlaylistConflict> {
+    let mut conflicts = Vec::<PlaylistConflict>::new();
+
+    playlists.retain_mut(|playlist| {
+        let disk_index = on_disk
+            .iter()
+            .position(|disk_playlist| disk_playlist.file_name == playlist.file_name);
+
+        match (disk_index, playlist.changed) {
+            (Some(index), false) => {
+                *playlist = on_disk.remove(index);
+                true
+            }
+            (Some(index), true) => {
+                let disk_playlist = on_disk.remove(index);
+                if disk_playlist.disk_content != playlist.disk_content {
+                    conflicts.push(PlaylistConflict {
+                        file_name: playlist.file_name.to_string(),
+                        on_disk: disk_playlist,
+                    });
+                }
+                true
+            }
+            (None, changed) => changed,
+        }
+    });
+
+    playlists.append(&mut on_disk);
+    conflicts
+}
+
+/// Settles a conflict either by keeping the edits, which will overwrite the file on
+/// the next save, or by replacing them with the version on disk.
+pub fn resolve_conflict(playlists: &mut [Playlist], conflict: PlaylistConflict, keep_edits: bool) {
+    if let Some(playlist) = playlists
+        .iter_mut()
+        .find(|playlist| playlist.file_name == conflict.file_name)
+    {
+        if keep_edits {
+            playlist.disk_content = conflict.on_disk.disk_content;
+        } else {
+            *playlist = conflict.on_disk;
+        }
+    }
+}
+
+/// What applying a playlist file that changed on disk did to the playlists.
+pub enum PlaylistChange {
+    /// The file matches what was loaded, like after saving it here.
+    Unchanged,
+    /// The playlist was added, replaced or removed.
+    Applied,
+    Conflict(Box<PlaylistConflict>),
+}
+
+/// Applies one playlist file that changed on disk, `on_disk` being `None` when it was
+/// deleted. Follows the same rules as `merge_reloaded_playlists`.
+pub fn apply_playlist_change(
+    playlists: &mut Vec<Playlist>,
+    file_name: &str,
+    on_disk: Option<Playlist>,
+) -> PlaylistChange {
+    let local_index = playlists
+        .iter()
+        .position(|playlist| playlist.file_name == file_name);
+
+    match (local_index, on_disk) {
+        (Some(index), Some(disk_playlist)) => {
+            let playlist = &mut playlists[index];
+            if disk_playlist.disk_content == playlist.disk_content {
+                PlaylistChange::Unchanged
+            } else if !playlist.changed {
+                *playlist = disk_playlist;
+                PlaylistChange::Applied
+            } else {
+                PlaylistChange::Conflict(Box::new(PlaylistConflict {
+                    file_name: file_name.to_string(),
+                    on_disk: disk_playlist,
+                }))
+            }
+        }
+        (Some(index), None) if !playlists[index].changed => {
+            playlists.remove(index);
+            PlaylistChange::Applied
+        }
+        (None, Some(disk_playlist)) => {
+            playlists.push(disk_playlist);
+            PlaylistChange::Applied
+        }
+        _ => PlaylistChange::Unchanged,
+    }
+}
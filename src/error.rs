@@ -0,0 +1,215 @@
+use std::{error::Error, fmt, io};
+
+/// Everything that can go wrong while reading or writing the library.
+#[derive(Debug)]
+pub enum LibraryError {
+    SongsFolder(io::Error),
+    PlaylistsFolder(io::Error),
+    ReadInfoDat {
+        folder: String,
+        source: io::Error,
+    },
+    ParseInfoDat {
+        folder: String,
+        source: serde_json::Error,
+    },
+    ReadLevelFile {
+        folder: String,
+        file_name: String,
+        source: io::Error,
+    },
+    ReadPlaylist {
+        file_name: String,
+        source: io::Error,
+    },
+    ParsePlaylist {
+        file_name: String,
+        source: serde_json::Error,
+    },
+    SerializePlaylist {
+        file_name: String,
+        source: serde_json::Error,
+    },
+    WritePlaylist {
+        file_name: String,
+        source: io::Error,
+    },
+    DeletePlaylist {
+        file_name: String,
+        source: io::Error,
+    },
+    Backup(io::Error),
+    RestoreBackup(io::Error),
+    ReadCache(io::Error),
+    ParseCache(serde_json::Error),
+    SerializeCache(serde_json::Error),
+    WriteCache(io::Error),
+    Watch(notify::Error),
+    ReadSettings(io::Error),
+    ParseSettings(serde_json::Error),
+    SerializeSettings(serde_json::Error),
+    WriteSettings(io::Error),
+    ReadBlist {
+        file_name: String,
+        source: zip::result::ZipError,
+    },
+    WriteBlist {
+        file_name: String,
+        source: zip::result::ZipError,
+    },
+    ReadCover {
+        path: String,
+        source: io::Error,
+    },
+    CoverFormat(String),
+    CoverEncoding(base64::DecodeError),
+    DecodeCover(image::ImageError),
+    EncodeCover(image::ImageError),
+    CoverFont(ab_glyph::InvalidFont),
+    ReadBmbfConfig(io::Error),
+    ParseBmbfConfig(serde_json::Error),
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LibraryError::SongsFolder(err) => write!(f, "Can't open the songs folder: {}", err),
+            LibraryError::PlaylistsFolder(err) => {
+                write!(f, "Can't open the playlists folder: {}", err)
+            }
+            LibraryError::ReadInfoDat { folder, source } => {
+                write!(f, "{}: can't read Info.dat: {}", folder, source)
+            }
+            LibraryError::ParseInfoDat { folder, source } => {
+                write!(f, "{}: can't deserialize Info.dat: {}", folder, source)
+            }
+            LibraryError::ReadLevelFile {
+                folder,
+                file_name,
+                source,
+            } => write!(
+                f,
+                "{}: can't read {}, using folder name as hash: {}",
+                folder, file_name, source
+            ),
+            LibraryError::ReadPlaylist { file_name, source } => {
+                write!(f, "{}: can't read playlist: {}", file_name, source)
+            }
+            LibraryError::ParsePlaylist { file_name, source } => {
+                write!(f, "{}: can't deserialize playlist: {}", file_name, source)
+            }
+            LibraryError::SerializePlaylist { file_name, source } => {
+                write!(f, "{}: can't serialize playlist: {}", file_name, source)
+            }
+            LibraryError::WritePlaylist { file_name, source } => {
+                write!(f, "{}: can't save playlist: {}", file_name, source)
+            }
+            LibraryError::DeletePlaylist { file_name, source } => {
+                write!(f, "{}: can't delete playlist: {}", file_name, source)
+            }
+            LibraryError::Backup(err) => write!(f, "Can't back up previous versions: {}", err),
+            LibraryError::RestoreBackup(err) => write!(f, "Can't restore the backup: {}", err),
+            LibraryError::ReadCache(err) => write!(f, "Can't read the level cache: {}", err),
+            LibraryError::ParseCache(err) => {
+                write!(f, "Level cache is unreadable, rebuilding it: {}", err)
+            }
+            LibraryError::SerializeCache(err) => {
+                write!(f, "Can't serialize the level cache: {}", err)
+            }
+            LibraryError::WriteCache(err) => write!(f, "Can't write the level cache: {}", err),
+            LibraryError::Watch(err) => {
+                write!(f, "Can't watch the library for changes: {}", err)
+            }
+            LibraryError::ReadSettings(err) => write!(f, "Can't read the settings: {}", err),
+            LibraryError::ParseSettings(err) => {
+                write!(f, "Can't deserialize the settings: {}", err)
+            }
+            LibraryError::SerializeSettings(err) => {
+                write!(f, "Can't serialize the settings: {}", err)
+            }
+            LibraryError::WriteSettings(err) => write!(f, "Can't save the settings: {}", err),
+            LibraryError::ReadBlist { file_name, source } => {
+                write!(f, "{}: can't unpack playlist: {}", file_name, source)
+            }
+            LibraryError::WriteBlist { file_name, source } => {
+                write!(f, "{}: can't pack playlist: {}", file_name, source)
+            }
+            LibraryError::ReadCover { path, source } => {
+                write!(f, "{}: can't read cover: {}", path, source)
+            }
+            LibraryError::CoverFormat(path) => {
+                write!(f, "{}: covers have to be PNG or JPG images", path)
+            }
+            LibraryError::CoverEncoding(err) => write!(f, "Cover isn't valid base64: {}", err),
+            LibraryError::DecodeCover(err) => write!(f, "Can't decode cover: {}", err),
+            LibraryError::EncodeCover(err) => write!(f, "Can't encode cover: {}", err),
+            LibraryError::CoverFont(err) => write!(f, "Can't load the cover font: {}", err),
+            LibraryError::ReadBmbfConfig(err) => write!(f, "Can't read the BMBF config: {}", err),
+            LibraryError::ParseBmbfConfig(err) => {
+                write!(f, "Can't deserialize the BMBF config: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for LibraryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LibraryError::SongsFolder(err)
+            | LibraryError::PlaylistsFolder(err)
+            | LibraryError::Backup(err)
+            | LibraryError::RestoreBackup(err)
+            | LibraryError::ReadCache(err)
+            | LibraryError::WriteCache(err)
+            | LibraryError::ReadSettings(err)
+            | LibraryError::WriteSettings(err)
+            | LibraryError::ReadBmbfConfig(err)
+            | LibraryError::ReadCover { source: err, .. }
+            | LibraryError::ReadInfoDat { source: err, .. }
+            | LibraryError::ReadLevelFile { source: err, .. }
+            | LibraryError::ReadPlaylist { source: err, .. }
+            | LibraryError::WritePlaylist { source: err, .. }
+            | LibraryError::DeletePlaylist { source: err, .. } => Some(err),
+            LibraryError::ParseCache(err)
+            | LibraryError::SerializeCache(err)
+            | LibraryError::ParseSettings(err)
+            | LibraryError::SerializeSettings(err)
+            | LibraryError::ParseBmbfConfig(err)
+            | LibraryError::ParseInfoDat { source: err, .. }
+            | LibraryError::ParsePlaylist { source: err, .. }
+            | LibraryError::SerializePlaylist { source: err, .. } => Some(err),
+            LibraryError::ReadBlist { source: err, .. }
+            | LibraryError::WriteBlist { source: err, .. } => Some(err),
+            LibraryError::Watch(err) => Some(err),
+            LibraryError::CoverEncoding(err) => Some(err),
+            LibraryError::DecodeCover(err) | LibraryError::EncodeCover(err) => Some(err),
+            LibraryError::CoverFont(err) => Some(err),
+            LibraryError::CoverFormat(_) => None,
+        }
+    }
+}
+
+/// Folders and playlists that were skipped while loading, and anything else worth a look.
+#[derive(Default)]
+pub struct LoadReport {
+    pub skipped_levels: Vec<LibraryError>,
+    pub skipped_playlists: Vec<LibraryError>,
+    pub errors: Vec<LibraryError>,
+}
+
+impl LoadReport {
+    pub fn issues(&self) -> impl Iterator<Item = &LibraryError> {
+        self.errors
+            .iter()
+            .chain(self.skipped_levels.iter())
+            .chain(self.skipped_playlists.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len() + self.skipped_levels.len() + self.skipped_playlists.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,149 @@
+use serde::Deserialize;
+
+use crate::bmbf_utils::{Beatmap, BeatmapSet, CustomLevel};
+
+/// Info.dat layout, detected from the `_version`/`version` field.
+#[derive(PartialEq, Debug)]
+pub enum InfoDatVersion {
+    V2,
+    V4,
+}
+
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(alias = "_version")]
+    version: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InfoDatV4 {
+    version: String,
+    song: SongV4,
+    audio: AudioV4,
+    #[serde(default)]
+    cover_image_filename: String,
+    #[serde(default)]
+    difficulty_beatmaps: Vec<DifficultyBeatmapV4>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SongV4 {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    sub_title: String,
+    #[serde(default)]
+    author: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioV4 {
+    #[serde(default)]
+    bpm: f32,
+    audio_data_filename: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DifficultyBeatmapV4 {
+    characteristic: String,
+    difficulty: String,
+    #[serde(default)]
+    beatmap_authors: BeatmapAuthorsV4,
+    #[serde(default)]
+    note_jump_movement_speed: f32,
+    #[serde(default)]
+    note_jump_start_beat_offset: f32,
+    beatmap_data_filename: String,
+    lightshow_data_filename: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct BeatmapAuthorsV4 {
+    #[serde(default)]
+    mappers: Vec<String>,
+}
+
+pub fn detect_version(info_dat: &str) -> Result<InfoDatVersion, serde_json::Error> {
+    let probe = serde_json::from_str::<VersionProbe>(info_dat)?;
+
+    Ok(match probe.version {
+        Some(version) if version.starts_with('4') => InfoDatVersion::V4,
+        _ => InfoDatVersion::V2,
+    })
+}
+
+/// Reads a v2 or v4 Info.dat into the common `CustomLevel` model.
+pub fn parse_info_dat(info_dat: &str) -> Result<CustomLevel, serde_json::Error> {
+    match detect_version(info_dat)? {
+        InfoDatVersion::V2 => serde_json::from_str::<CustomLevel>(info_dat),
+        InfoDatVersion::V4 => serde_json::from_str::<InfoDatV4>(info_dat).map(CustomLevel::from),
+    }
+}
+
+impl From<InfoDatV4> for CustomLevel {
+    fn from(info: InfoDatV4) -> Self {
+        let level_author = info
+            .difficulty_beatmaps
+            .iter()
+            .flat_map(|beatmap| beatmap.beatmap_authors.mappers.iter())
+            .fold(Vec::<&str>::new(), |mut mappers, mapper| {
+                if !mappers.contains(&mapper.as_str()) {
+                    mappers.push(mapper);
+                }
+                mappers
+            })
+            .join(", ");
+
+        let mut beatmap_sets = Vec::<BeatmapSet>::new();
+        for beatmap in info.difficulty_beatmaps {
+            let converted = Beatmap {
+                difficulty_rank: difficulty_rank(&beatmap.difficulty),
+                difficulty: beatmap.difficulty,
+                file_name: beatmap.beatmap_data_filename,
+                lightshow_file_name: beatmap.lightshow_data_filename,
+                note_jump_speed: beatmap.note_jump_movement_speed,
+                note_jump_offset: beatmap.note_jump_start_beat_offset,
+            };
+
+            match beatmap_sets
+                .iter_mut()
+                .find(|set| set.characteristic == beatmap.characteristic)
+            {
+                Some(set) => set.beatmaps.push(converted),
+                None => beatmap_sets.push(BeatmapSet {
+                    characteristic: beatmap.characteristic,
+                    beatmaps: vec![converted],
+                }),
+            }
+        }
+
+        CustomLevel {
+            version: info.version,
+            song_name: info.song.title,
+            song_sub_name: info.song.sub_title,
+            song_author: info.song.author,
+            level_author,
+            cover_image_filename: info.cover_image_filename,
+            beats_per_minute: info.audio.bpm,
+            audio_data_file_name: info.audio.audio_data_filename,
+            beatmap_sets,
+            ..Default::default()
+        }
+    }
+}
+
+/// v4 dropped `_difficultyRank`, so derive it from the difficulty name the way v2 maps set it.
+fn difficulty_rank(difficulty: &str) -> u8 {
+    match difficulty {
+        "Easy" => 1,
+        "Normal" => 3,
+        "Hard" => 5,
+        "Expert" => 7,
+        "ExpertPlus" => 9,
+        _ => 0,
+    }
+}
@@ -0,0 +1,14 @@
+pub mod backend;
+pub mod backup;
+pub mod bmbf_config;
+pub mod bmbf_utils;
+pub mod cover_mosaic;
+pub mod covers;
+pub mod editing;
+pub mod error;
+pub mod info_dat;
+pub mod paths;
+pub mod playlist_format;
+pub mod scan;
+pub mod settings;
+pub mod watch;
@@ -1,9 +1,36 @@
 use levenshtein::levenshtein;
-use std::fmt;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+};
 
-use bmbf_utils::*;
+use playlist_creator::{
+    backend::{LibraryBackend, LocalBackend},
+    backup::{self, Snapshot},
+    bmbf_config::read_bmbf_playlists,
+    bmbf_utils::*,
+    cover_mosaic::generate_cover,
+    editing::{
+        self, apply_playlist_change, create_playlist, delete_playlist, import_playlists,
+        is_level_assigned, merge_reloaded_playlists, playlist_levels, playlists_containing,
+        rename_playlist, resolve_conflict, PlaylistChange, PlaylistConflict,
+    },
+    error::{LibraryError, LoadReport},
+    playlist_format::encode_image_file,
+    scan::{LevelReader, Scan, ScanEvent},
+    settings::{load_settings, save_settings, settings_path, LibraryRoot, Settings},
+    watch::{LibraryChange, LibraryWatcher},
+};
 
-pub mod bmbf_utils;
+mod cli;
+mod cover_cache;
+
+use cover_cache::CoverCache;
 
 #[derive(PartialEq)]
 enum Sorting {
@@ -13,93 +40,144 @@ enum Sorting {
     NameAsc,
     ModifiedDsc,
     ModifiedAsc,
+    NJSDsc,
+    NJSAsc,
 }
 
+const CHARACTERISTICS: [&str; 7] = [
+    "Standard",
+    "OneSaber",
+    "NoArrows",
+    "90Degree",
+    "360Degree",
+    "Lightshow",
+    "Lawless",
+];
+const DIFFICULTIES: [&str; 5] = ["Easy", "Normal", "Hard", "Expert", "ExpertPlus"];
+const WIP_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 160, 40);
+const BADGE_COLOR: egui::Color32 = egui::Color32::from_rgb(60, 80, 110);
+/// Pixel sizes covers are decoded at, for list rows and for the level details.
+const THUMBNAIL_SIZE: u32 = 64;
+const COVER_SIZE: u32 = 256;
+
 struct App {
+    settings: Settings,
+    /// Name of the library root picked in the top bar.
+    root_name: Option<String>,
+    /// BMBF config found on the picked device, its playlists can be imported.
+    bmbf_config: Option<PathBuf>,
+    import_message: Option<String>,
+    /// `None` while the picked device isn't connected.
+    backend: Option<Arc<dyn LibraryBackend>>,
     custom_levels: Vec<CustomLevel>,
-    available_levels: Vec<CustomLevel>,
+    library_levels: Vec<CustomLevel>,
     selected_level: Option<usize>,
     playlists: Vec<Playlist>,
     selected_playlist: Option<usize>,
     selected_song: Option<usize>,
     text_input: String,
     level_search: String,
+    /// Hides levels that are already in a playlist.
+    only_unassigned: bool,
     create_new_playlist: bool,
+    create_playlist_error: Option<String>,
     sort: Sorting,
+    characteristic_filter: Option<&'static str>,
+    difficulty_filter: Option<&'static str>,
+    save_outcomes: Vec<SaveOutcome>,
+    save_error: Option<LibraryError>,
+    snapshots: Option<Vec<Snapshot>>,
+    load_report: LoadReport,
+    reload_conflicts: Vec<PlaylistConflict>,
+    /// Removed playlists whose files are deleted on the next save.
+    deleted_playlists: Vec<Playlist>,
+    /// File name of the playlist waiting for the user to confirm its deletion.
+    confirm_delete: Option<String>,
+    /// Library root to open once the unsaved changes are saved or discarded.
+    pending_root: Option<Option<String>>,
+    /// Playlist the title and cover inputs below were filled in for.
+    metadata_playlist: Option<usize>,
+    title_input: String,
+    cover_path: String,
+    metadata_error: Option<String>,
+    /// File name of the playlist whose cover is being generated, and where it arrives.
+    generated_cover: Option<(String, Receiver<Result<String, LibraryError>>)>,
+    scan: Option<Scan>,
+    watcher: Option<LibraryWatcher>,
+    /// Reads the level folders the watcher reports.
+    level_reader: Option<LevelReader>,
+    /// Level folders that changed while a scan ran, read once it finished.
+    deferred_levels: Vec<String>,
+    covers: Option<CoverCache>,
 }
 
 fn main() {
-    let mut custom_levels = get_custom_levels();
-    println!("CustomLevels size: {}", custom_levels.len());
-    //custom_levels.sort_by(|a, b| a.modified.cmp(&b.modified));
-    for level in custom_levels.iter() {
-        println!(
-            "Song name: {}, modified: {}",
-            level.song_name,
-            level.modified.unwrap_or(0)
-        );
+    let args: Vec<String> = std::env::args().collect();
+    if cli::is_cli_invocation(&args) {
+        if let Err(err) = cli::run(&args) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
     }
+
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(840., 480.)),
         ..Default::default()
     };
 
-    let playlists = get_playlists();
-    playlists.iter().for_each(|playlist| {
-        println!("{}", playlist.title);
-    });
-
-    let p_songs: usize = playlists.iter().map(|playlist| playlist.songs.len()).sum();
-
-    println!("Number of songs in all playlists: {}", p_songs);
-    println!("Custom levels total: {}", custom_levels.len());
+    let (settings, settings_error) = match load_settings() {
+        Ok(settings) => (settings, None),
+        Err(err) => (Settings::default(), Some(err)),
+    };
+    let mut app = App::new(settings);
+    if let Some(err) = settings_error {
+        app.load_report.errors.push(err);
+    }
 
-    let mut available_levels: Vec<CustomLevel> = custom_levels
-        .iter()
-        .cloned()
-        .filter(|level| {
-            !playlists.iter().any(|playlist| {
-                playlist.songs.iter().any(|playlist_song| {
-                    level.hash.is_some() && playlist_song.hash.eq(level.hash.as_ref().unwrap())
-                })
-            })
-        })
-        .collect();
-
-    println!(
-        "Custom levels that are not in any playlists: {}",
-        available_levels.len()
-    );
-
-    available_levels.sort_by(|level_1, level_2| level_1.modified.cmp(&level_2.modified));
-    available_levels.reverse();
-
-    eframe::run_native(
-        "Playlist Creator",
-        options,
-        Box::new(|_cc| {
-            Box::new(App {
-                custom_levels,
-                available_levels,
-                playlists,
-                selected_level: None,
-                selected_playlist: None,
-                selected_song: None,
-                text_input: "name".to_owned(),
-                level_search: "".to_owned(),
-                create_new_playlist: false,
-                sort: Sorting::ModifiedDsc,
-            })
-        }),
-    );
+    eframe::run_native("Playlist Creator", options, Box::new(|_cc| Box::new(app)));
 }
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_scan(ctx);
+        self.poll_watcher(ctx);
+        self.poll_generated_cover(ctx);
+        if let Some(covers) = self.covers.as_mut() {
+            if covers.poll(ctx) {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            }
+        }
+
+        egui::TopBottomPanel::top("library_panel").show(ctx, |ui| {
+            self.show_root_picker(ui);
+        });
+        self.show_unsaved_window(ctx);
+
+        if self.backend.is_none() {
+            self.show_no_device(ctx);
+            return;
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             let text_style = egui::TextStyle::Body;
             let row_height = ui.text_style_height(&text_style);
 
+            if !self.load_report.is_empty() {
+                egui::TopBottomPanel::top("issues_panel").show_inside(ui, |ui| {
+                    egui::CollapsingHeader::new(format!("Issues ({})", self.load_report.len()))
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical()
+                                .max_height(120.0)
+                                .show(ui, |ui| {
+                                    for issue in self.load_report.issues() {
+                                        ui.label(issue.to_string());
+                                    }
+                                });
+                        });
+                });
+            }
+
             egui::SidePanel::left("left_panel")
                 .resizable(true)
                 .default_width(300.0)
@@ -140,14 +218,44 @@ impl eframe::App for App {
                                             Sorting::ModifiedDsc,
                                             "Created Descending",
                                         ),
+                                        ui.selectable_value(
+                                            &mut self.sort,
+                                            Sorting::NJSAsc,
+                                            "NJS Ascending",
+                                        ),
+                                        ui.selectable_value(
+                                            &mut self.sort,
+                                            Sorting::NJSDsc,
+                                            "NJS Descending",
+                                        ),
                                     ];
                                     if responses.iter().any(|response| response.clicked()) {
                                         self.sort();
                                     }
                                 });
 
-                            ui.button("Force reload");
+                            if ui.button("Force reload").clicked() {
+                                self.force_reload();
+                            }
                         });
+                        if let Some(scan) = self.scan.as_ref().filter(|scan| !scan.finished) {
+                            ui.horizontal(|ui| {
+                                let fraction = if scan.total > 0 {
+                                    scan.done as f32 / scan.total as f32
+                                } else {
+                                    0.0
+                                };
+                                if ui.button("Cancel").clicked() {
+                                    scan.cancel();
+                                }
+                                ui.add(
+                                    egui::ProgressBar::new(fraction).text(format!(
+                                        "{} / {} levels read",
+                                        scan.done, scan.total
+                                    )),
+                                );
+                            });
+                        }
                         ui.horizontal(|ui| {
                             let search_response =
                                 ui.add(egui::TextEdit::singleline(&mut self.level_search));
@@ -155,17 +263,55 @@ impl eframe::App for App {
                             if !self.level_search.is_empty() && search_response.changed() {
                                 self.levenshtein_sort();
                             }
+                            ui.checkbox(&mut self.only_unassigned, "Only unassigned");
+                        });
+                        ui.horizontal(|ui| {
+                            filter_combo_box(
+                                ui,
+                                "characteristic_combo_box",
+                                &mut self.characteristic_filter,
+                                &CHARACTERISTICS,
+                            );
+                            filter_combo_box(
+                                ui,
+                                "difficulty_combo_box",
+                                &mut self.difficulty_filter,
+                                &DIFFICULTIES,
+                            );
                         });
                     });
+
+                    egui::TopBottomPanel::bottom("level_details")
+                        .resizable(true)
+                        .show_inside(ui, |ui| {
+                            egui::ScrollArea::vertical().show(ui, |ui| {
+                                match self
+                                    .selected_level
+                                    .and_then(|index| self.library_levels.get(index))
+                                {
+                                    Some(level) => {
+                                        let cover = self.covers.as_mut().and_then(|covers| {
+                                            covers.level_cover(level, COVER_SIZE)
+                                        });
+                                        show_level_details(ui, level, cover.map(|cover| cover.id()))
+                                    }
+                                    None => {
+                                        ui.label("Select a level to see its details.");
+                                    }
+                                }
+                            });
+                        });
+
+                    let visible_levels = self.filtered_levels();
                     egui::ScrollArea::vertical().show(ui, |ui| {
                         egui::ScrollArea::vertical().show_rows(
                             ui,
                             row_height,
-                            self.available_levels.len(),
+                            visible_levels.len(),
                             |ui, range| {
-                                for row in range {
+                                for row in range.map(|row| visible_levels[row]) {
                                     let text = self
-                                        .available_levels
+                                        .library_levels
                                         .get(row)
                                         .map(|level| {
                                             format!(
@@ -181,15 +327,51 @@ impl eframe::App for App {
                                         .selected_level
                                         .map(|index| index == row)
                                         .unwrap_or_else(|| false);
+                                    let is_wip = self
+                                        .library_levels
+                                        .get(row)
+                                        .is_some_and(CustomLevel::is_wip);
 
-                                    if ui
-                                        .add(
-                                            egui::SelectableLabel::new(is_selected, &text), //.sense(Sense::click()),
-                                        )
-                                        .clicked()
-                                    {
-                                        self.selected_level = Some(row);
-                                    }
+                                    let badges: Vec<&str> = self
+                                        .library_levels
+                                        .get(row)
+                                        .map(|level| {
+                                            playlists_containing(level, &self.playlists)
+                                                .map(|playlist| playlist.title.as_str())
+                                                .collect()
+                                        })
+                                        .unwrap_or_default();
+
+                                    let cover = self
+                                        .library_levels
+                                        .get(row)
+                                        .zip(self.covers.as_mut())
+                                        .and_then(|(level, covers)| {
+                                            covers.level_cover(level, THUMBNAIL_SIZE)
+                                        })
+                                        .map(|cover| cover.id());
+
+                                    ui.horizontal(|ui| {
+                                        show_thumbnail(ui, cover, row_height);
+                                        if is_wip {
+                                            ui.colored_label(WIP_COLOR, "WIP");
+                                        }
+                                        if ui
+                                            .add(
+                                                egui::SelectableLabel::new(is_selected, &text), //.sense(Sense::click()),
+                                            )
+                                            .clicked()
+                                        {
+                                            self.selected_level = Some(row);
+                                        }
+                                        for title in badges {
+                                            ui.label(
+                                                egui::RichText::new(title)
+                                                    .small()
+                                                    .background_color(BADGE_COLOR),
+                                            );
+                                        }
+                                    });
                                 }
                             },
                         );
@@ -206,26 +388,72 @@ impl eframe::App for App {
                                 self.create_new_playlist = true;
                             }
                             if let Some(selected_playlist) = self.get_selected_playlist() {
-                                if selected_playlist.just_created && ui.button("-").clicked() {
-                                    self.remove_selected_playlist();
+                                if ui.button("-").clicked() {
+                                    // Nothing is on the device yet for new playlists.
+                                    if selected_playlist.just_created {
+                                        self.remove_selected_playlist();
+                                    } else {
+                                        self.confirm_delete =
+                                            Some(selected_playlist.file_name.to_string());
+                                    }
                                 }
                             }
 
                             if ui.button("Save to device").clicked() {
-                                save_modified_playlists(&self.playlists);
+                                self.save_playlists();
+                            }
+
+                            if ui.button("Restore backup").clicked() {
+                                if let Some(backend) = &self.backend {
+                                    self.snapshots = Some(backup::list_snapshots(backend.as_ref()));
+                                }
+                            }
+
+                            if self.bmbf_config.is_some()
+                                && ui
+                                    .button("Import BMBF")
+                                    .on_hover_text("Add the playlists kept in BMBF's config")
+                                    .clicked()
+                            {
+                                self.import_bmbf_playlists();
                             }
                         });
+                        if let Some(message) = &self.import_message {
+                            ui.label(message);
+                        }
+                        if !self.deleted_playlists.is_empty() {
+                            ui.label(format!(
+                                "{} deleted playlists are removed on save",
+                                self.deleted_playlists.len()
+                            ));
+                        }
+                        if let Some(err) = &self.save_error {
+                            ui.colored_label(egui::Color32::RED, err.to_string());
+                        }
+                        for outcome in self.save_outcomes.iter() {
+                            match &outcome.result {
+                                Ok(_) if outcome.deleted => {
+                                    ui.label(format!("Deleted {}", outcome.file_name))
+                                }
+                                Ok(_) => ui.label(format!("Saved {}", outcome.file_name)),
+                                Err(err) => ui.colored_label(egui::Color32::RED, err.to_string()),
+                            };
+                        }
                         if self.create_new_playlist {
                             ui.add(egui::TextEdit::singleline(&mut self.text_input));
                             ui.horizontal(|ui| {
                                 if ui.button("Cancel").clicked() {
                                     self.create_new_playlist = false;
+                                    self.create_playlist_error = None;
                                 }
 
                                 if ui.button("Add").clicked() {
                                     self.create_new_playlist();
                                 }
                             });
+                            if let Some(err) = &self.create_playlist_error {
+                                ui.colored_label(egui::Color32::RED, err);
+                            }
                         }
                         ui.heading("Playlists:");
                     });
@@ -242,16 +470,34 @@ impl eframe::App for App {
                                         .map(|index| index == row)
                                         .unwrap_or_else(|| false);
 
-                                    if ui
-                                        .add(egui::SelectableLabel::new(
-                                            is_selected,
-                                            playlist.title.to_string(),
-                                        ))
-                                        .clicked()
-                                    {
-                                        self.selected_playlist = Some(row);
-                                        self.selected_song = None;
-                                    }
+                                    let changed_on_disk = self
+                                        .reload_conflicts
+                                        .iter()
+                                        .any(|conflict| conflict.file_name == playlist.file_name);
+                                    let text = if changed_on_disk {
+                                        format!("{} (changed on device)", playlist.title)
+                                    } else {
+                                        playlist.title.to_string()
+                                    };
+
+                                    let cover = self
+                                        .covers
+                                        .as_mut()
+                                        .and_then(|covers| {
+                                            covers.playlist_cover(playlist, THUMBNAIL_SIZE)
+                                        })
+                                        .map(|cover| cover.id());
+
+                                    ui.horizontal(|ui| {
+                                        show_thumbnail(ui, cover, row_height);
+                                        if ui
+                                            .add(egui::SelectableLabel::new(is_selected, text))
+                                            .clicked()
+                                        {
+                                            self.selected_playlist = Some(row);
+                                            self.selected_song = None;
+                                        }
+                                    });
                                 }
                             }
                         },
@@ -263,18 +509,45 @@ impl eframe::App for App {
                 .min_height(0.0)
                 .show_inside(ui, |ui| {
                     ui.horizontal_centered(|ui| {
-                        if ui.button(">>").clicked() {
+                        let can_add = self
+                            .selected_level
+                            .and_then(|index| self.library_levels.get(index))
+                            .zip(self.get_selected_playlist())
+                            .is_some_and(|(level, playlist)| {
+                                !is_playlist_contains_song(playlist, Song::from_level(level))
+                            });
+                        if ui.add_enabled(can_add, egui::Button::new(">>")).clicked() {
                             self.add_selected_song_to_selected_playlist();
-                            self.selected_level = None;
                         }
                         if ui.button("X").clicked() {
                             self.remove_selected_song_from_selected_playlist();
                             self.selected_song = None;
                         }
+                        if ui.button("Up").clicked() {
+                            self.move_selected_song(-1);
+                        }
+                        if ui.button("Down").clicked() {
+                            self.move_selected_song(1);
+                        }
                     });
                 });
 
+            self.show_backups_window(ctx);
+            self.show_conflict_window(ctx);
+            self.show_delete_window(ctx);
+
             egui::CentralPanel::default().show_inside(ui, |ui| {
+                if self.selected_song.is_some() {
+                    egui::TopBottomPanel::bottom("song_difficulties")
+                        .resizable(true)
+                        .show_inside(ui, |ui| self.show_song_difficulties(ui));
+                }
+
+                if self.selected_playlist.is_some() {
+                    egui::TopBottomPanel::top("playlist_metadata")
+                        .show_inside(ui, |ui| self.show_playlist_metadata(ui));
+                }
+
                 if let Some(playlist) = self
                     .selected_playlist
                     .and_then(|index| self.playlists.get(index))
@@ -294,11 +567,25 @@ impl eframe::App for App {
                                         .map(|index| index == row)
                                         .unwrap_or_else(|| false);
 
+                                    let text = if song.difficulties.is_empty() {
+                                        song.name.to_string()
+                                    } else {
+                                        format!(
+                                            "{} [{}]",
+                                            song.name,
+                                            song.difficulties
+                                                .iter()
+                                                .map(|difficulty| format!(
+                                                    "{} {}",
+                                                    difficulty.characteristic, difficulty.name
+                                                ))
+                                                .collect::<Vec<String>>()
+                                                .join(", ")
+                                        )
+                                    };
+
                                     if ui
-                                        .add(egui::SelectableLabel::new(
-                                            is_selected,
-                                            song.name.to_string(),
-                                        ))
+                                        .add(egui::SelectableLabel::new(is_selected, text))
                                         .clicked()
                                     {
                                         self.selected_song = Some(row)
@@ -314,8 +601,258 @@ impl eframe::App for App {
 }
 
 impl App {
+    fn new(settings: Settings) -> Self {
+        let root_name = settings.default_root().map(|root| root.name.clone());
+        let mut app = App {
+            settings,
+            root_name: None,
+            bmbf_config: None,
+            import_message: None,
+            backend: None,
+            custom_levels: Vec::new(),
+            library_levels: Vec::new(),
+            playlists: Vec::new(),
+            selected_level: None,
+            selected_playlist: None,
+            selected_song: None,
+            text_input: "name".to_owned(),
+            level_search: "".to_owned(),
+            only_unassigned: false,
+            create_new_playlist: false,
+            create_playlist_error: None,
+            sort: Sorting::ModifiedDsc,
+            characteristic_filter: None,
+            difficulty_filter: None,
+            save_outcomes: Vec::new(),
+            save_error: None,
+            snapshots: None,
+            load_report: LoadReport::default(),
+            reload_conflicts: Vec::new(),
+            deleted_playlists: Vec::new(),
+            confirm_delete: None,
+            pending_root: None,
+            metadata_playlist: None,
+            title_input: String::new(),
+            cover_path: String::new(),
+            metadata_error: None,
+            generated_cover: None,
+            scan: None,
+            watcher: None,
+            level_reader: None,
+            deferred_levels: Vec::new(),
+            covers: None,
+        };
+        app.open_root(
+            root_name.or_else(|| app.settings.roots.first().map(|root| root.name.clone())),
+        );
+        app
+    }
+
+    /// Switches to another library root, dropping everything loaded from the previous one.
+    fn open_root(&mut self, root_name: Option<String>) {
+        self.scan = None;
+        self.watcher = None;
+        self.level_reader = None;
+        self.deferred_levels.clear();
+        self.covers = None;
+        self.custom_levels.clear();
+        self.library_levels.clear();
+        self.playlists.clear();
+        self.selected_level = None;
+        self.selected_playlist = None;
+        self.selected_song = None;
+        self.save_outcomes.clear();
+        self.save_error = None;
+        self.snapshots = None;
+        self.load_report = LoadReport::default();
+        self.reload_conflicts.clear();
+        self.deleted_playlists.clear();
+        self.confirm_delete = None;
+        self.pending_root = None;
+        self.generated_cover = None;
+
+        let root = root_name
+            .as_deref()
+            .and_then(|name| self.settings.find_root(name))
+            .and_then(|root| Some((root.resolve()?, root.layout)));
+        self.bmbf_config = root
+            .as_ref()
+            .map(|(path, _)| path.join(BMBF_CONFIG_PATH))
+            .filter(|path| path.is_file());
+        self.import_message = None;
+        let backend = root.map(|(path, layout)| LocalBackend::from_layout(path, layout));
+        self.root_name = root_name;
+        let Some(backend) = backend else {
+            self.backend = None;
+            return;
+        };
+        let backend: Arc<dyn LibraryBackend> = Arc::new(backend);
+        self.covers = Some(CoverCache::new(Arc::clone(&backend)));
+
+        match get_playlists(backend.as_ref(), &mut self.load_report) {
+            Ok(playlists) => self.playlists = playlists,
+            Err(err) => self.load_report.errors.push(err),
+        }
+        self.watcher = match LibraryWatcher::start(backend.as_ref()) {
+            Some(Ok(watcher)) => Some(watcher),
+            Some(Err(err)) => {
+                self.load_report.errors.push(LibraryError::Watch(err));
+                None
+            }
+            None => None,
+        };
+        self.backend = Some(backend);
+        self.start_scan(true);
+
+        if self.settings.selected != self.root_name {
+            self.settings.selected = self.root_name.clone();
+            if let Err(err) = save_settings(&self.settings) {
+                self.load_report.errors.push(err);
+            }
+        }
+    }
+
+    fn show_root_picker(&mut self, ui: &mut egui::Ui) {
+        let mut picked = None;
+
+        ui.horizontal(|ui| {
+            ui.label("Library:");
+            egui::ComboBox::from_id_source("root_combo_box")
+                .selected_text(self.root_name.as_deref().unwrap_or("None"))
+                .show_ui(ui, |ui| {
+                    for root in self.settings.roots.iter() {
+                        let is_selected = self.root_name.as_ref() == Some(&root.name);
+                        if ui.selectable_label(is_selected, &root.name).clicked() && !is_selected {
+                            picked = Some(root.name.clone());
+                        }
+                    }
+                });
+
+            if self.backend.is_none() {
+                ui.colored_label(egui::Color32::RED, "No device found");
+            }
+        });
+
+        if picked.is_some() {
+            self.switch_root(picked);
+        }
+    }
+
+    /// Opens another library root, asking first what to do with unsaved changes.
+    fn switch_root(&mut self, root_name: Option<String>) {
+        if self.unsaved_changes() > 0 {
+            self.pending_root = Some(root_name);
+        } else {
+            self.open_root(root_name);
+        }
+    }
+
+    /// Edited, created or imported playlists and deletions not saved yet.
+    fn unsaved_changes(&self) -> usize {
+        self.playlists
+            .iter()
+            .filter(|playlist| playlist.changed)
+            .count()
+            + self.deleted_playlists.len()
+    }
+
+    fn show_unsaved_window(&mut self, ctx: &egui::Context) {
+        let Some(root_name) = &self.pending_root else {
+            return;
+        };
+
+        let mut save = None;
+        let mut cancel = false;
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} playlists have unsaved changes, they are lost when {} is opened.",
+                    self.unsaved_changes(),
+                    root_name.as_deref().unwrap_or("another library")
+                ));
+                ui.horizontal(|ui| {
+                    if self.backend.is_some() && ui.button("Save").clicked() {
+                        save = Some(true);
+                    }
+                    if ui.button("Discard").clicked() {
+                        save = Some(false);
+                    }
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if cancel {
+            self.pending_root = None;
+        }
+        let Some(save) = save else {
+            return;
+        };
+        if save {
+            self.save_playlists();
+        }
+        let root_name = self.pending_root.take().flatten();
+        // Failed saves stay, with their errors shown next to the playlists.
+        if !save || self.unsaved_changes() == 0 {
+            self.open_root(root_name);
+        }
+    }
+
+    fn show_no_device(&mut self, ctx: &egui::Context) {
+        let mut retry = false;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading("No device found");
+                match self
+                    .root_name
+                    .as_deref()
+                    .and_then(|name| self.settings.find_root(name))
+                {
+                    Some(LibraryRoot {
+                        path,
+                        device: Some(device),
+                        ..
+                    }) => ui.label(format!(
+                        "Nothing matching \"{}\" is mounted in {}. Connect the headset and allow file access.",
+                        device,
+                        path.display()
+                    )),
+                    Some(root) => ui.label(format!("{} doesn't exist.", root.path.display())),
+                    None => ui.label("No libraries are configured."),
+                };
+                ui.label(format!(
+                    "Libraries are configured in {}.",
+                    settings_path().display()
+                ));
+                retry = ui.button("Retry").clicked();
+
+                for issue in self.load_report.issues() {
+                    ui.colored_label(egui::Color32::RED, issue.to_string());
+                }
+            });
+        });
+
+        if retry {
+            // The settings may have been edited in the meantime.
+            let settings_error = match load_settings() {
+                Ok(settings) => {
+                    self.settings = settings;
+                    None
+                }
+                Err(err) => Some(err),
+            };
+            let root_name = self.root_name.clone().or_else(|| {
+                let root = self.settings.default_root();
+                root.map(|root| root.name.clone())
+            });
+            self.switch_root(root_name);
+            self.load_report.errors.extend(settings_error);
+        }
+    }
+
     fn levenshtein_sort(&mut self) {
-        self.available_levels.sort_by(|level_1, level_2| {
+        self.library_levels.sort_by(|level_1, level_2| {
             levenshtein(&level_1.song_name, &self.level_search)
                 .cmp(&levenshtein(&level_2.song_name, &self.level_search))
         })
@@ -325,24 +862,29 @@ impl App {
         match self.sort {
             Sorting::BPMDsc => {
                 self.sort_bpm();
-                self.available_levels.reverse();
+                self.library_levels.reverse();
             }
             Sorting::BPMAsc => self.sort_bpm(),
             Sorting::NameDsc => {
                 self.sort_name();
-                self.available_levels.reverse();
+                self.library_levels.reverse();
             }
             Sorting::NameAsc => self.sort_name(),
             Sorting::ModifiedDsc => {
                 self.sort_modified();
-                self.available_levels.reverse();
+                self.library_levels.reverse();
             }
             Sorting::ModifiedAsc => self.sort_modified(),
+            Sorting::NJSDsc => {
+                self.sort_njs();
+                self.library_levels.reverse();
+            }
+            Sorting::NJSAsc => self.sort_njs(),
         }
     }
 
     fn sort_bpm(&mut self) {
-        self.available_levels.sort_by(|level_1, level_2| {
+        self.library_levels.sort_by(|level_1, level_2| {
             level_1
                 .beats_per_minute
                 .total_cmp(&level_2.beats_per_minute)
@@ -350,35 +892,519 @@ impl App {
     }
 
     fn sort_name(&mut self) {
-        self.available_levels
+        self.library_levels
             .sort_by(|level_1, level_2| level_1.song_name.cmp(&level_2.song_name));
     }
 
     fn sort_modified(&mut self) {
-        self.available_levels
-            .sort_by(|level_1, level_2| level_1.modified.cmp(&level_2.modified))
+        self.library_levels.sort_by_key(|level| level.modified)
     }
 
-    fn create_new_playlist(&mut self) {
-        let title = self.text_input.to_string();
+    fn sort_njs(&mut self) {
+        self.library_levels.sort_by(|level_1, level_2| {
+            level_1
+                .max_note_jump_speed()
+                .total_cmp(&level_2.max_note_jump_speed())
+        });
+    }
 
-        if !self
-            .playlists
+    fn filtered_levels(&self) -> Vec<usize> {
+        self.library_levels
             .iter()
-            .any(|playlist| playlist.title.eq(&title))
-        {
-            let new_playlist = Playlist {
-                file_name: format!("{}.json", title),
-                changed: true,
-                just_created: true,
-                songs: Vec::new(),
-                title,
-                ..Default::default()
-            };
-            self.playlists.push(new_playlist);
-            self.create_new_playlist = false;
+            .enumerate()
+            .filter(|(_, level)| {
+                self.characteristic_filter
+                    .map(|characteristic| level.has_characteristic(characteristic))
+                    .unwrap_or(true)
+                    && self
+                        .difficulty_filter
+                        .map(|difficulty| level.has_difficulty(difficulty))
+                        .unwrap_or(true)
+                    && !(self.only_unassigned && is_level_assigned(level, &self.playlists))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn create_new_playlist(&mut self) {
+        match create_playlist(&mut self.playlists, &self.text_input) {
+            Ok(_) => {
+                self.create_new_playlist = false;
+                self.create_playlist_error = None;
+            }
+            Err(err) => self.create_playlist_error = Some(err),
+        }
+    }
+
+    fn save_playlists(&mut self) {
+        let Some(backend) = &self.backend else {
+            return;
+        };
+
+        match save_modified_playlists(
+            backend.as_ref(),
+            &mut self.playlists,
+            &mut self.deleted_playlists,
+        ) {
+            Ok(outcomes) => {
+                self.save_outcomes = outcomes;
+                self.save_error = None;
+            }
+            Err(err) => {
+                self.save_outcomes = Vec::new();
+                self.save_error = Some(err);
+            }
+        }
+    }
+
+    /// Adds the playlists from BMBF's config as unsaved playlists.
+    fn import_bmbf_playlists(&mut self) {
+        let Some(path) = &self.bmbf_config else {
+            return;
+        };
+
+        match read_bmbf_playlists(path) {
+            Ok(imported) => {
+                let count = imported.len();
+                let skipped = import_playlists(&mut self.playlists, imported);
+                self.import_message = Some(if skipped.is_empty() {
+                    format!("Imported {} playlists, save them to the device", count)
+                } else {
+                    format!(
+                        "Imported {} playlists, skipped existing {}",
+                        count - skipped.len(),
+                        skipped.join(", ")
+                    )
+                });
+                self.refresh_library_levels();
+            }
+            Err(err) => self.import_message = Some(err.to_string()),
+        }
+    }
+
+    fn show_backups_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.snapshots.is_some();
+        let mut restored = None;
+
+        if let Some(snapshots) = &self.snapshots {
+            egui::Window::new("Backups")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    if snapshots.is_empty() {
+                        ui.label("No backups yet.");
+                    }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (index, snapshot) in snapshots.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "{}, {} playlists",
+                                    snapshot.date(),
+                                    snapshot.file_names.len()
+                                ))
+                                .on_hover_text(snapshot.file_names.join("\n"));
+                                if ui.button("Restore").clicked() {
+                                    restored = Some(index);
+                                }
+                            });
+                        }
+                    });
+                });
+        }
+
+        let restored = restored
+            .and_then(|index| self.snapshots.as_ref()?.get(index))
+            .zip(self.backend.as_ref());
+        if let Some((snapshot, backend)) = restored {
+            match backup::restore_snapshot(backend.as_ref(), snapshot) {
+                Ok(_) => {
+                    let file_names = snapshot.file_names.clone();
+                    self.save_error = None;
+                    self.reload_restored_playlists(&file_names);
+                }
+                Err(err) => self.save_error = Some(LibraryError::RestoreBackup(err)),
+            }
+            open = false;
+        }
+
+        if !open {
+            self.snapshots = None;
+        }
+    }
+
+    /// Re-reads the playlists after a backup was restored, keeping unsaved edits. The
+    /// restored files are no longer deleted on save, and edited playlists whose file
+    /// was restored become conflicts to resolve.
+    fn reload_restored_playlists(&mut self, restored: &[String]) {
+        let Some(backend) = self.backend.clone() else {
+            return;
+        };
+
+        let selection = self.playlist_selection();
+        self.deleted_playlists
+            .retain(|playlist| !restored.contains(&playlist.file_name));
+
+        let mut report = LoadReport::default();
+        match get_playlists(backend.as_ref(), &mut report) {
+            Ok(mut on_disk) => {
+                on_disk.retain(|playlist| !self.is_pending_deletion(&playlist.file_name));
+                let conflicts = merge_reloaded_playlists(&mut self.playlists, on_disk);
+                self.reload_conflicts.retain(|pending| {
+                    !conflicts
+                        .iter()
+                        .any(|conflict| conflict.file_name == pending.file_name)
+                });
+                self.reload_conflicts.extend(conflicts);
+            }
+            Err(err) => report.errors.push(err),
+        }
+        self.load_report.skipped_playlists = report.skipped_playlists;
+        self.load_report.errors.append(&mut report.errors);
+        self.restore_playlist_selection(selection);
+    }
+
+    /// Rescans levels bypassing the cache and re-reads playlists, keeping unsaved edits.
+    fn force_reload(&mut self) {
+        let Some(backend) = &self.backend else {
+            return;
+        };
+
+        let mut report = LoadReport::default();
+        match get_playlists(backend.as_ref(), &mut report) {
+            Ok(mut on_disk) => {
+                on_disk.retain(|playlist| !self.is_pending_deletion(&playlist.file_name));
+                self.reload_conflicts = merge_reloaded_playlists(&mut self.playlists, on_disk)
+            }
+            Err(err) => report.errors.push(err),
+        }
+        self.load_report = report;
+        self.start_scan(false);
+    }
+
+    fn start_scan(&mut self, use_cache: bool) {
+        self.custom_levels.clear();
+        self.refresh_library_levels();
+        // The scan reads every folder, reads still under way would only add them twice.
+        self.deferred_levels.clear();
+        self.level_reader = self
+            .backend
+            .as_ref()
+            .map(|backend| LevelReader::start(Arc::clone(backend)));
+        self.scan = self
+            .backend
+            .as_ref()
+            .map(|backend| Scan::start(Arc::clone(backend), use_cache));
+    }
+
+    /// Takes in levels read by the background scan so far.
+    fn poll_scan(&mut self, ctx: &egui::Context) {
+        let Some((events, finished)) = self.scan.as_mut().map(|scan| (scan.poll(), scan.finished))
+        else {
+            return;
+        };
+
+        let mut received = false;
+        for event in events {
+            match event {
+                ScanEvent::Level(level) => {
+                    self.library_levels.push(level.clone());
+                    self.custom_levels.push(level);
+                    received = true;
+                }
+                ScanEvent::Finished(mut report, result) => {
+                    if let Some(reader) = &self.level_reader {
+                        self.deferred_levels
+                            .drain(..)
+                            .for_each(|folder| reader.read(folder));
+                    }
+                    self.load_report
+                        .skipped_levels
+                        .append(&mut report.skipped_levels);
+                    self.load_report.errors.append(&mut report.errors);
+                    if let Err(err) = result {
+                        self.load_report.errors.push(err);
+                    }
+                }
+                ScanEvent::Progress { .. } => {}
+            }
+        }
+
+        if received {
+            self.resort_keeping_selection();
+        }
+
+        if !finished {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Sets the cover generated in the background on the playlist it was made for.
+    fn poll_generated_cover(&mut self, ctx: &egui::Context) {
+        let Some((file_name, receiver)) = &self.generated_cover else {
+            return;
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                return;
+            }
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.generated_cover = None;
+                return;
+            }
+        };
+
+        match result {
+            Ok(image) => {
+                if let Some(playlist) = self
+                    .playlists
+                    .iter_mut()
+                    .find(|playlist| &playlist.file_name == file_name)
+                {
+                    playlist.image = Some(image);
+                    playlist.changed = true;
+                }
+            }
+            Err(err) => self.metadata_error = Some(err.to_string()),
+        }
+        self.generated_cover = None;
+    }
+
+    /// Re-sorts the level list after levels came in, keeping the selected level selected.
+    fn resort_keeping_selection(&mut self) {
+        let selected_hash = self.selected_level_hash();
+        self.resort_selecting(selected_hash);
+    }
+
+    fn selected_level_hash(&self) -> Option<String> {
+        self.selected_level
+            .and_then(|index| self.library_levels.get(index))
+            .and_then(|level| level.hash.clone())
+    }
+
+    /// Re-sorts the level list and selects the level with `selected_hash`, for when
+    /// levels were removed from the list since the hash was taken.
+    fn resort_selecting(&mut self, selected_hash: Option<String>) {
+        if self.level_search.is_empty() {
+            self.sort();
         } else {
-            println!("Playlist with the same title already exists!");
+            self.levenshtein_sort();
+        }
+
+        self.selected_level = selected_hash.and_then(|hash| {
+            self.library_levels
+                .iter()
+                .position(|level| level.hash.as_ref() == Some(&hash))
+        });
+    }
+
+    /// Applies changes the watcher saw in the songs and playlists directories.
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        let Some(changes) = self.watcher.as_mut().map(|watcher| watcher.poll()) else {
+            return;
+        };
+        let Some(backend) = self.backend.clone() else {
+            return;
+        };
+
+        // Taken before re-read folders leave the list and shift the selected index.
+        let selected_hash = self.selected_level_hash();
+        let mut levels_changed = false;
+        for read in self
+            .level_reader
+            .as_ref()
+            .map(|reader| reader.poll())
+            .unwrap_or_default()
+        {
+            if let Some(covers) = self.covers.as_mut() {
+                covers.forget_level(&read.folder);
+            }
+            let is_other_folder = |level: &CustomLevel| level.folder.as_ref() != Some(&read.folder);
+            self.custom_levels.retain(is_other_folder);
+            self.library_levels.retain(is_other_folder);
+
+            if let Some(level) = read.level {
+                self.library_levels.push(level.clone());
+                self.custom_levels.push(level);
+            }
+            let mut report = read.report;
+            self.load_report
+                .skipped_levels
+                .append(&mut report.skipped_levels);
+            self.load_report.errors.append(&mut report.errors);
+            levels_changed = true;
+        }
+
+        let selection = self.playlist_selection();
+        let mut playlists_changed = false;
+        for change in changes {
+            match change {
+                LibraryChange::Level(folder) => {
+                    let is_scanning = self.scan.as_ref().is_some_and(|scan| !scan.finished);
+                    if is_scanning {
+                        if !self.deferred_levels.contains(&folder) {
+                            self.deferred_levels.push(folder);
+                        }
+                    } else if let Some(reader) = &self.level_reader {
+                        reader.read(folder);
+                    }
+                }
+                LibraryChange::Playlist(file_name) if self.is_pending_deletion(&file_name) => {}
+                LibraryChange::Playlist(file_name) => {
+                    match get_playlist(backend.as_ref(), &file_name) {
+                        Ok(on_disk) => {
+                            match apply_playlist_change(&mut self.playlists, &file_name, on_disk) {
+                                PlaylistChange::Unchanged => {}
+                                PlaylistChange::Applied => playlists_changed = true,
+                                PlaylistChange::Conflict(conflict) => {
+                                    self.reload_conflicts
+                                        .retain(|pending| pending.file_name != conflict.file_name);
+                                    self.reload_conflicts.push(*conflict);
+                                }
+                            }
+                        }
+                        Err(err) => self.load_report.skipped_playlists.push(err),
+                    }
+                }
+            }
+        }
+
+        if playlists_changed {
+            self.restore_playlist_selection(selection);
+        }
+        if levels_changed {
+            self.resort_selecting(selected_hash);
+        }
+
+        // Watcher events arrive on another thread, so keep checking for them.
+        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+    }
+
+    /// File name of the selected playlist and hash of its selected song, which stay
+    /// valid when playlists are added or removed around them.
+    fn playlist_selection(&self) -> (Option<String>, Option<String>) {
+        let playlist = self.get_selected_playlist();
+        let song = playlist
+            .zip(self.selected_song)
+            .and_then(|(playlist, index)| playlist.songs.get(index));
+
+        (
+            playlist.map(|playlist| playlist.file_name.to_string()),
+            song.map(|song| song.hash.to_string()),
+        )
+    }
+
+    fn restore_playlist_selection(
+        &mut self,
+        (file_name, song_hash): (Option<String>, Option<String>),
+    ) {
+        let was_shown = self.metadata_playlist == self.selected_playlist;
+        self.selected_playlist = file_name.and_then(|file_name| {
+            self.playlists
+                .iter()
+                .position(|playlist| playlist.file_name == file_name)
+        });
+        self.selected_song =
+            self.get_selected_playlist()
+                .zip(song_hash)
+                .and_then(|(playlist, hash)| {
+                    playlist
+                        .songs
+                        .iter()
+                        .position(|song| song.hash.eq_ignore_ascii_case(&hash))
+                });
+        // Keeps the title being typed when only the playlist's position moved.
+        if was_shown {
+            self.metadata_playlist = self.selected_playlist;
+        }
+    }
+
+    fn refresh_library_levels(&mut self) {
+        self.library_levels = self.custom_levels.clone();
+        self.selected_playlist = None;
+        self.selected_song = None;
+        self.selected_level = None;
+        self.sort();
+    }
+
+    fn show_conflict_window(&mut self, ctx: &egui::Context) {
+        let Some(conflict) = self.reload_conflicts.first() else {
+            return;
+        };
+
+        let mut keep_edits = None;
+        egui::Window::new("Playlist changed on disk")
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} was changed on the device while you were editing it.",
+                    conflict.file_name
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Keep my edits").clicked() {
+                        keep_edits = Some(true);
+                    }
+                    if ui.button("Use version on device").clicked() {
+                        keep_edits = Some(false);
+                    }
+                });
+            });
+
+        if let Some(keep_edits) = keep_edits {
+            let selection = self.playlist_selection();
+            let conflict = self.reload_conflicts.remove(0);
+            resolve_conflict(&mut self.playlists, conflict, keep_edits);
+            // The version on disk may have other songs, the selected one is looked up again.
+            if !keep_edits {
+                self.restore_playlist_selection(selection);
+            }
+        }
+    }
+
+    fn show_song_difficulties(&mut self, ui: &mut egui::Ui) {
+        let Some(song) = self.selected_playlist.zip(self.selected_song).and_then(
+            |(playlist_index, song_index)| {
+                self.playlists.get(playlist_index)?.songs.get(song_index)
+            },
+        ) else {
+            return;
+        };
+
+        ui.strong("Highlighted difficulties:");
+        let Some(level) = self.find_level_by_hash(&song.hash) else {
+            ui.label("Level is not on the device.");
+            return;
+        };
+
+        let mut toggled = None;
+        for set in level.beatmap_sets.iter() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(&set.characteristic);
+                for beatmap in set.beatmaps.iter() {
+                    let mut checked = song.targets(&set.characteristic, &beatmap.difficulty);
+                    if ui.checkbox(&mut checked, &beatmap.difficulty).changed() {
+                        toggled = Some((
+                            set.characteristic.to_string(),
+                            beatmap.difficulty.to_string(),
+                        ));
+                    }
+                }
+            });
+        }
+
+        if let Some((characteristic, difficulty)) = toggled {
+            if let Some(playlist) = self
+                .selected_playlist
+                .and_then(|index| self.playlists.get_mut(index))
+            {
+                if let Some(song) = self
+                    .selected_song
+                    .and_then(|index| playlist.songs.get_mut(index))
+                {
+                    song.toggle_difficulty(&characteristic, &difficulty);
+                    playlist.changed = true;
+                }
+            }
         }
     }
 
@@ -388,33 +1414,196 @@ impl App {
     }
 
     fn remove_selected_playlist(&mut self) {
-        if let Some(playlist) = self.get_selected_playlist() {
-            let mut levels: Vec<CustomLevel> = playlist
-                .songs
-                .iter()
-                .flat_map(|song| self.find_level_by_hash(&song.hash))
-                .collect();
-            self.available_levels.append(&mut levels);
-            self.sort();
+        if let Some(mut playlist) = self
+            .selected_playlist
+            .and_then(|index| delete_playlist(&mut self.playlists, index))
+        {
+            // A renamed playlist is still on the device under its old name.
+            if let Some(previous) = playlist.previous_file_name.take() {
+                playlist.file_name = previous;
+            }
+            if !playlist.just_created {
+                self.deleted_playlists.push(playlist);
+            }
+            self.selected_playlist = None;
+            self.selected_song = None;
         }
+    }
 
-        if let Some(index) = self.selected_playlist {
-            self.playlists.remove(index);
-            self.selected_playlist = None;
+    fn show_playlist_metadata(&mut self, ui: &mut egui::Ui) {
+        if self.metadata_playlist != self.selected_playlist {
+            self.metadata_playlist = self.selected_playlist;
+            self.title_input = self
+                .get_selected_playlist()
+                .map(|playlist| playlist.title.to_string())
+                .unwrap_or_default();
+            self.cover_path.clear();
+            self.metadata_error = None;
         }
+        let Some(index) = self.selected_playlist else {
+            return;
+        };
+
+        egui::CollapsingHeader::new("Playlist details").show(ui, |ui| {
+            egui::Grid::new("playlist_metadata_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Title");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.title_input);
+                        if ui.button("Rename").clicked() {
+                            self.metadata_error =
+                                rename_playlist(&mut self.playlists, index, &self.title_input)
+                                    .err();
+                        }
+                    });
+                    ui.end_row();
+
+                    let Some(playlist) = self.playlists.get_mut(index) else {
+                        return;
+                    };
+
+                    ui.label("File");
+                    match &playlist.previous_file_name {
+                        Some(previous) => ui.label(format!(
+                            "{}, renamed from {} on save",
+                            playlist.file_name, previous
+                        )),
+                        None => ui.label(&playlist.file_name),
+                    };
+                    ui.end_row();
+
+                    ui.label("Author");
+                    let mut author = playlist.author.clone().unwrap_or_default();
+                    if ui.text_edit_singleline(&mut author).changed() {
+                        playlist.author = Some(author).filter(|author| !author.is_empty());
+                        playlist.changed = true;
+                    }
+                    ui.end_row();
+
+                    ui.label("Description");
+                    let mut description = playlist.description.clone().unwrap_or_default();
+                    if ui.text_edit_multiline(&mut description).changed() {
+                        playlist.description =
+                            Some(description).filter(|description| !description.is_empty());
+                        playlist.changed = true;
+                    }
+                    ui.end_row();
+
+                    ui.label("Cover");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.cover_path)
+                                .hint_text("Path to a PNG or JPG"),
+                        );
+                        if ui.button("Set").clicked() {
+                            match encode_image_file(Path::new(self.cover_path.trim())) {
+                                Ok(image) => {
+                                    playlist.image = Some(image);
+                                    playlist.changed = true;
+                                    self.metadata_error = None;
+                                }
+                                Err(err) => self.metadata_error = Some(err.to_string()),
+                            }
+                        }
+                        let generating = self.generated_cover.is_some();
+                        if ui
+                            .add_enabled(!generating, egui::Button::new("Generate"))
+                            .on_hover_text("Make a cover from the covers of the playlist's levels")
+                            .clicked()
+                        {
+                            if let Some(backend) = &self.backend {
+                                let backend = Arc::clone(backend);
+                                let levels = playlist_levels(playlist, &self.custom_levels);
+                                let title = playlist.title.to_string();
+                                let (sender, receiver) = mpsc::channel();
+                                thread::spawn(move || {
+                                    let _ = sender.send(generate_cover(
+                                        backend.as_ref(),
+                                        &levels,
+                                        &title,
+                                    ));
+                                });
+                                self.generated_cover =
+                                    Some((playlist.file_name.to_string(), receiver));
+                                self.metadata_error = None;
+                            }
+                        }
+                        if generating {
+                            ui.spinner();
+                        }
+                        if playlist.image.is_some() && ui.button("Remove").clicked() {
+                            playlist.image = None;
+                            playlist.changed = true;
+                        }
+                    });
+                    ui.end_row();
+                });
+
+            if let Some(err) = &self.metadata_error {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        });
     }
 
-    fn find_level_by_hash(&self, hash: &str) -> Option<CustomLevel> {
-        self.custom_levels
+    /// Files that are removed on the next save: of deleted playlists, and the old
+    /// names of renamed ones.
+    fn is_pending_deletion(&self, file_name: &str) -> bool {
+        self.deleted_playlists
             .iter()
-            .find(|level| {
-                if let Some(level_hash) = &level.hash {
-                    level_hash.eq(hash)
-                } else {
-                    false
-                }
-            })
-            .cloned()
+            .any(|playlist| playlist.file_name == file_name)
+            || self
+                .playlists
+                .iter()
+                .any(|playlist| playlist.previous_file_name.as_deref() == Some(file_name))
+    }
+
+    fn show_delete_window(&mut self, ctx: &egui::Context) {
+        // Looked up by file name, the watcher may add or remove playlists meanwhile.
+        let Some((index, playlist)) = self.confirm_delete.as_ref().and_then(|file_name| {
+            self.playlists
+                .iter()
+                .enumerate()
+                .find(|(_, playlist)| &playlist.file_name == file_name)
+        }) else {
+            self.confirm_delete = None;
+            return;
+        };
+
+        let mut confirmed = None;
+        egui::Window::new("Delete playlist")
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Delete {}? {} is removed from the device on the next save, \
+                     a backup copy is kept.",
+                    playlist.title,
+                    playlist
+                        .previous_file_name
+                        .as_ref()
+                        .unwrap_or(&playlist.file_name)
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Delete").clicked() {
+                        confirmed = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        confirmed = Some(false);
+                    }
+                });
+            });
+
+        if let Some(confirmed) = confirmed {
+            if confirmed {
+                self.selected_playlist = Some(index);
+                self.remove_selected_playlist();
+            }
+            self.confirm_delete = None;
+        }
+    }
+
+    fn find_level_by_hash(&self, hash: &str) -> Option<CustomLevel> {
+        editing::find_level_by_hash(&self.custom_levels, hash).cloned()
     }
 
     fn add_selected_song_to_selected_playlist(&mut self) {
@@ -423,19 +1612,9 @@ impl App {
         {
             if let (Some(playlist), Some(level)) = (
                 self.playlists.get_mut(playlist_index),
-                self.available_levels.get(level_index),
+                self.library_levels.get(level_index),
             ) {
-                playlist.songs.push(Song {
-                    name: level.song_name.to_string(),
-                    hash: level
-                        .hash
-                        .as_ref()
-                        .unwrap_or(&"Unknown".to_string())
-                        .to_string(),
-                });
-
-                playlist.changed = true;
-                self.available_levels.remove(level_index);
+                playlist.add_level(level);
             }
         }
     }
@@ -444,47 +1623,92 @@ impl App {
         if let (Some(playlist_index), Some(song_index)) =
             (self.selected_playlist, self.selected_song)
         {
-            // if let Some(playlist) = self.playlists.get_mut(playlist_index) {
-            //     if let Some(_song) = playlist.songs.get(song_index) {
-            //         let song = playlist.songs.remove(song_index);
-            //         if let Some(custom_level) = self.custom_levels.iter().find(|level| {
-            //             level
-            //                 .hash
-            //                 .as_ref()
-            //                 .unwrap_or(&"unknown".to_string())
-            //                 .eq(&song.hash)
-            //         }) {
-            //             self.available_levels.push(custom_level.clone());
-            //         }
-            //     }
-            // }
-            // self.selected_playlist.zip(self.selected_song).iter().for_each(|(playlist_index, song_index)|{
-
-            // });
+            if let Some(playlist) = self.playlists.get_mut(playlist_index) {
+                playlist.remove_song(song_index);
+            }
+        }
+    }
 
-            self.playlists
+    fn move_selected_song(&mut self, offset: isize) {
+        if let (Some(playlist_index), Some(song_index)) =
+            (self.selected_playlist, self.selected_song)
+        {
+            let target = song_index.saturating_add_signed(offset);
+            if self
+                .playlists
                 .get_mut(playlist_index)
-                .map(|playlist| {
-                    playlist.changed = true;
-                    playlist.songs.remove(song_index)
-                })
-                .and_then(|song| {
-                    self.custom_levels.iter().find(|level| {
-                        level
-                            .hash
-                            .as_ref()
-                            .unwrap_or(&"unknown".to_string())
-                            .eq(&song.hash)
-                    })
-                })
-                .into_iter()
-                .for_each(|level| {
-                    self.available_levels.push(level.clone());
-                })
+                .map(|playlist| playlist.move_song(song_index, target))
+                .unwrap_or(false)
+            {
+                self.selected_song = Some(target);
+            }
+        }
+    }
+}
+
+fn filter_combo_box(
+    ui: &mut egui::Ui,
+    id: &str,
+    filter: &mut Option<&'static str>,
+    values: &[&'static str],
+) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(filter.unwrap_or("Any"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(filter, None, "Any");
+            for value in values {
+                ui.selectable_value(filter, Some(*value), *value);
+            }
+        });
+}
+
+/// A square cover next to a list row, or the empty space it would take.
+fn show_thumbnail(ui: &mut egui::Ui, cover: Option<egui::TextureId>, size: f32) {
+    match cover {
+        Some(cover) => {
+            ui.image(cover, egui::vec2(size, size));
+        }
+        None => {
+            ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
         }
     }
 }
 
+fn show_level_details(ui: &mut egui::Ui, level: &CustomLevel, cover: Option<egui::TextureId>) {
+    if let Some(cover) = cover {
+        ui.image(cover, egui::vec2(128.0, 128.0));
+    }
+    ui.heading(&level.song_name);
+    if level.is_wip() {
+        ui.colored_label(WIP_COLOR, "Work in progress level");
+    }
+    if !level.song_sub_name.is_empty() {
+        ui.label(&level.song_sub_name);
+    }
+    ui.label(format!("Artist: {}", level.song_author));
+    ui.label(format!("Mapper: {}", level.level_author));
+    ui.label(format!("BPM: {}", level.beats_per_minute));
+
+    for set in level.beatmap_sets.iter() {
+        ui.separator();
+        ui.strong(&set.characteristic);
+        egui::Grid::new(format!("beatmaps_{}", set.characteristic))
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Difficulty");
+                ui.label("NJS");
+                ui.label("Offset");
+                ui.end_row();
+                for beatmap in set.beatmaps.iter() {
+                    ui.label(&beatmap.difficulty);
+                    ui.label(format!("{}", beatmap.note_jump_speed));
+                    ui.label(format!("{}", beatmap.note_jump_offset));
+                    ui.end_row();
+                }
+            });
+    }
+}
+
 impl fmt::Display for Sorting {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -494,6 +1718,8 @@ impl fmt::Display for Sorting {
             Sorting::NameAsc => write!(f, "Name /\\"),
             Sorting::ModifiedDsc => write!(f, "Created \\/"),
             Sorting::ModifiedAsc => write!(f, "Created /\\"),
+            Sorting::NJSDsc => write!(f, "NJS \\/"),
+            Sorting::NJSAsc => write!(f, "NJS /\\"),
         }
     }
 }
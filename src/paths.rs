@@ -0,0 +1,37 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+const APP_NAME: &str = "playlist_creator";
+
+/// `$XDG_CACHE_HOME/playlist_creator`, falling back to `~/.cache/playlist_creator`.
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", ".cache")
+}
+
+/// `$XDG_CONFIG_HOME/playlist_creator`, falling back to `~/.config/playlist_creator`.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `$XDG_DATA_HOME/playlist_creator`, falling back to `~/.local/share/playlist_creator`.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Short stable name for a device identifier, usable as a file or folder name.
+pub fn device_key(device_id: &str) -> String {
+    let digest = sha1_smol::Sha1::from(device_id).digest().to_string();
+    digest[..12].to_string()
+}
+
+fn xdg_dir(variable: &str, home_fallback: &str) -> PathBuf {
+    let base = env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(home_fallback)))
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    base.join(APP_NAME)
+}
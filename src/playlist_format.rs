@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Cursor, Read, Write},
+    path::Path,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    bmbf_utils::{Playlist, Song, SongDifficulty, CUSTOM_LEVEL_PREFIX},
+    error::LibraryError,
+};
+
+/// The playlist inside a `.blist` archive.
+const BLIST_PLAYLIST_FILE: &str = "playlist.json";
+const BLIST_COVER_FILE: &str = "cover.png";
+/// Where BeatSaver puts the cover, instead of `imageString`.
+const IMAGE_KEY: &str = "image";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG";
+const JPG_SIGNATURE: &[u8] = b"\xFF\xD8\xFF";
+
+/// Playlist file formats, told apart by their extension.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlaylistFormat {
+    Json,
+    /// Same schema as `.json`, the extension PC mods use.
+    Bplist,
+    /// Zip with a `playlist.json` in the Blister schema and the cover next to it.
+    Blist,
+}
+
+impl PlaylistFormat {
+    /// `None` for files that aren't playlists, like covers or `.DS_Store`.
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "json" => Some(PlaylistFormat::Json),
+            "bplist" => Some(PlaylistFormat::Bplist),
+            "blist" => Some(PlaylistFormat::Blist),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a playlist file. Returns the playlist and the JSON text it was read
+/// from, which tells later whether the file changed on disk.
+pub fn decode_playlist(file_name: &str, bytes: &[u8]) -> Result<(Playlist, String), LibraryError> {
+    let parse_error = |source| LibraryError::ParsePlaylist {
+        file_name: file_name.to_string(),
+        source,
+    };
+
+    match PlaylistFormat::from_file_name(file_name) {
+        Some(PlaylistFormat::Blist) => {
+            let blist_error = |source| LibraryError::ReadBlist {
+                file_name: file_name.to_string(),
+                source,
+            };
+            let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(blist_error)?;
+
+            let mut content = String::new();
+            archive
+                .by_name(BLIST_PLAYLIST_FILE)
+                .map_err(blist_error)?
+                .read_to_string(&mut content)
+                .map_err(|source| blist_error(source.into()))?;
+            let blist: Blist = serde_json::from_str(&content).map_err(parse_error)?;
+
+            let mut cover = Vec::new();
+            if let Some(cover_file) = &blist.cover {
+                archive
+                    .by_name(cover_file)
+                    .map_err(blist_error)?
+                    .read_to_end(&mut cover)
+                    .map_err(|source| blist_error(source.into()))?;
+            }
+
+            Ok((blist.into_playlist(cover), content))
+        }
+        _ => {
+            let content = String::from_utf8_lossy(bytes).into_owned();
+            let mut playlist: Playlist = serde_json::from_str(&content).map_err(parse_error)?;
+            if playlist.image.is_none() {
+                if let Some(serde_json::Value::String(image)) = playlist.extra.remove(IMAGE_KEY) {
+                    playlist.image = Some(image);
+                    playlist.image_key_is_image = true;
+                }
+            }
+            Ok((playlist, content))
+        }
+    }
+}
+
+/// Serializes a playlist in the format its file name asks for. Returns the file
+/// contents and the JSON text to compare later reads against.
+pub fn encode_playlist(playlist: &Playlist) -> Result<(Vec<u8>, String), LibraryError> {
+    let serialize_error = |source| LibraryError::SerializePlaylist {
+        file_name: playlist.file_name.to_string(),
+        source,
+    };
+
+    match PlaylistFormat::from_file_name(&playlist.file_name) {
+        Some(PlaylistFormat::Blist) => {
+            let cover = playlist
+                .image
+                .as_deref()
+                .and_then(|image| decode_image_string(image).ok());
+            let blist = Blist::from_playlist(playlist, cover.is_some());
+            let content = serde_json::to_string(&blist).map_err(serialize_error)?;
+
+            let write_blist = || -> zip::result::ZipResult<Vec<u8>> {
+                let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+                writer.start_file(BLIST_PLAYLIST_FILE, FileOptions::default())?;
+                writer.write_all(content.as_bytes())?;
+                if let Some(cover) = &cover {
+                    writer.start_file(BLIST_COVER_FILE, FileOptions::default())?;
+                    writer.write_all(cover)?;
+                }
+                Ok(writer.finish()?.into_inner())
+            };
+            let bytes = write_blist().map_err(|source| LibraryError::WriteBlist {
+                file_name: playlist.file_name.to_string(),
+                source,
+            })?;
+
+            Ok((bytes, content))
+        }
+        _ => {
+            let mut value = serde_json::to_value(playlist).map_err(serialize_error)?;
+            if playlist.image_key_is_image {
+                if let Some(image) = value
+                    .as_object_mut()
+                    .and_then(|map| map.remove("imageString"))
+                {
+                    value[IMAGE_KEY] = image;
+                }
+            }
+            let content = serde_json::to_string(&value).map_err(serialize_error)?;
+            Ok((content.clone().into_bytes(), content))
+        }
+    }
+}
+
+/// Image bytes from an `imageString`, which may carry a `data:` URL prefix.
+pub fn decode_image_string(image: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    let data = match image.split_once("base64,") {
+        Some((_, data)) => data,
+        None => image,
+    };
+    STANDARD.decode(data.trim())
+}
+
+/// Reads a PNG or JPG image and encodes it for `imageString`.
+pub fn encode_image_file(path: &Path) -> Result<String, LibraryError> {
+    let bytes = fs::read(path).map_err(|source| LibraryError::ReadCover {
+        path: path.display().to_string(),
+        source,
+    })?;
+
+    if !bytes.starts_with(PNG_SIGNATURE) && !bytes.starts_with(JPG_SIGNATURE) {
+        return Err(LibraryError::CoverFormat(path.display().to_string()));
+    }
+
+    Ok(STANDARD.encode(bytes))
+}
+
+#[derive(Deserialize, Serialize)]
+struct Blist {
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cover: Option<String>,
+    #[serde(default)]
+    maps: Vec<BlistMap>,
+    #[serde(rename = "customData")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    custom_data: Option<HashMap<String, serde_json::Value>>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct BlistMap {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(rename = "levelID")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level_id: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    difficulties: Vec<SongDifficulty>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Blist {
+    fn into_playlist(self, cover: Vec<u8>) -> Playlist {
+        Playlist {
+            title: self.title,
+            author: self.author,
+            description: self.description,
+            image: Some(cover)
+                .filter(|cover| !cover.is_empty())
+                .map(|cover| STANDARD.encode(cover)),
+            custom_data: self.custom_data,
+            extra: self.extra,
+            songs: self
+                .maps
+                .into_iter()
+                .map(|map| {
+                    let hash = map.hash.or_else(|| {
+                        let level_id = map.level_id.as_deref()?;
+                        level_id
+                            .strip_prefix(CUSTOM_LEVEL_PREFIX)
+                            .map(str::to_string)
+                    });
+                    Song {
+                        hash: hash.unwrap_or_default(),
+                        key: map.key,
+                        level_id: map.level_id,
+                        difficulties: map.difficulties,
+                        extra: map.extra,
+                        ..Default::default()
+                    }
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn from_playlist(playlist: &Playlist, has_cover: bool) -> Self {
+        Blist {
+            title: playlist.title.to_string(),
+            author: playlist.author.clone(),
+            description: playlist.description.clone(),
+            cover: has_cover.then(|| BLIST_COVER_FILE.to_string()),
+            custom_data: playlist.custom_data.clone(),
+            extra: playlist.extra.clone(),
+            maps: playlist
+                .songs
+                .iter()
+                .map(|song| BlistMap {
+                    kind: if song.hash.is_empty() && song.key.is_some() {
+                        "key".to_string()
+                    } else {
+                        "hash".to_string()
+                    },
+                    hash: Some(song.hash.to_string()).filter(|hash| !hash.is_empty()),
+                    key: song.key.clone(),
+                    level_id: song.level_id.clone(),
+                    difficulties: song.difficulties.clone(),
+                    extra: song.extra.clone(),
+                })
+                .collect(),
+        }
+    }
+}
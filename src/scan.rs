@@ -0,0 +1,155 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{
+    backend::LibraryBackend,
+    bmbf_utils::{get_custom_level, scan_custom_levels, CustomLevel},
+    error::{LibraryError, LoadReport},
+};
+
+pub enum ScanEvent {
+    Level(CustomLevel),
+    Progress { done: usize, total: usize },
+    Finished(LoadReport, Result<(), LibraryError>),
+}
+
+/// A level scan running on a worker thread. Dropping it cancels the scan.
+pub struct Scan {
+    receiver: Receiver<ScanEvent>,
+    cancelled: Arc<AtomicBool>,
+    pub done: usize,
+    pub total: usize,
+    pub finished: bool,
+}
+
+impl Scan {
+    pub fn start(backend: Arc<dyn LibraryBackend>, use_cache: bool) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let worker_cancelled = Arc::clone(&cancelled);
+
+        thread::spawn(move || {
+            let mut report = LoadReport::default();
+            let result = scan_custom_levels(
+                backend.as_ref(),
+                &mut report,
+                use_cache,
+                &mut |level, done, total| {
+                    if let Some(level) = level {
+                        if sender.send(ScanEvent::Level(level)).is_err() {
+                            return false;
+                        }
+                    }
+                    sender.send(ScanEvent::Progress { done, total }).is_ok()
+                        && !worker_cancelled.load(Ordering::Relaxed)
+                },
+            );
+            let _ = sender.send(ScanEvent::Finished(report, result));
+        });
+
+        Scan {
+            receiver,
+            cancelled,
+            done: 0,
+            total: 0,
+            finished: false,
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns the events that arrived since the last call, keeping the progress counters current.
+    pub fn poll(&mut self) -> Vec<ScanEvent> {
+        let mut events = Vec::<ScanEvent>::new();
+
+        loop {
+            match self.receiver.try_recv() {
+                Ok(ScanEvent::Progress { done, total }) => {
+                    self.done = done;
+                    self.total = total;
+                }
+                Ok(event) => {
+                    if let ScanEvent::Finished(..) = event {
+                        self.finished = true;
+                    }
+                    events.push(event);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+
+        events
+    }
+}
+
+impl Drop for Scan {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// A level folder read again, `level` being `None` when it is gone or unreadable.
+pub struct LevelRead {
+    pub folder: String,
+    pub level: Option<CustomLevel>,
+    pub report: LoadReport,
+}
+
+/// Reads single level folders on a worker thread, hashing their files doesn't
+/// stall the UI. The folders are read in the order they were requested.
+pub struct LevelReader {
+    sender: Sender<String>,
+    receiver: Receiver<LevelRead>,
+}
+
+impl LevelReader {
+    pub fn start(backend: Arc<dyn LibraryBackend>) -> Self {
+        let (sender, folders) = mpsc::channel::<String>();
+        let (results, receiver) = mpsc::channel();
+
+        // Ends once the reader, and with it the sender, is dropped.
+        thread::spawn(move || {
+            for folder in folders {
+                let mut report = LoadReport::default();
+                let level = get_custom_level(backend.as_ref(), &folder, &mut report);
+                if results
+                    .send(LevelRead {
+                        folder,
+                        level,
+                        report,
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        LevelReader { sender, receiver }
+    }
+
+    pub fn read(&self, folder: String) {
+        let _ = self.sender.send(folder);
+    }
+
+    /// Folders read since the last call.
+    pub fn poll(&self) -> Vec<LevelRead> {
+        self.receiver.try_iter().collect()
+    }
+}
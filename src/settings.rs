@@ -0,0 +1,126 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{Layout, LocalBackend},
+    bmbf_utils::BASE_PATH,
+    error::LibraryError,
+    paths,
+};
+
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// A library the user can pick: a headset mounted through gvfs, a PC install or
+/// a folder with a copy of the headset's files.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct LibraryRoot {
+    pub name: String,
+    pub path: PathBuf,
+    /// When set, `path` holds device mounts and the first one whose name
+    /// contains this text (ignoring case) is the library root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    #[serde(default)]
+    pub layout: Layout,
+}
+
+impl LibraryRoot {
+    /// The library's root directory, or `None` when the device isn't connected
+    /// or the folder doesn't exist.
+    pub fn resolve(&self) -> Option<PathBuf> {
+        match &self.device {
+            Some(device) => find_device_folder(&self.path, device),
+            None => Some(self.path.clone()).filter(|path| path.is_dir()),
+        }
+    }
+
+    pub fn backend(&self) -> Option<LocalBackend> {
+        self.resolve()
+            .map(|root| LocalBackend::from_layout(root, self.layout))
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Settings {
+    pub roots: Vec<LibraryRoot>,
+    /// Name of the root opened last.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            roots: vec![LibraryRoot {
+                name: "Quest".to_string(),
+                path: PathBuf::from(BASE_PATH),
+                device: Some("quest".to_string()),
+                layout: Layout::Quest,
+            }],
+            selected: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn find_root(&self, name: &str) -> Option<&LibraryRoot> {
+        self.roots.iter().find(|root| root.name == name)
+    }
+
+    /// The root opened last if it's available, otherwise the first available one.
+    pub fn default_root(&self) -> Option<&LibraryRoot> {
+        self.selected
+            .as_deref()
+            .and_then(|name| self.find_root(name))
+            .filter(|root| root.resolve().is_some())
+            .or_else(|| self.roots.iter().find(|root| root.resolve().is_some()))
+    }
+}
+
+pub fn settings_path() -> PathBuf {
+    paths::config_dir().join(SETTINGS_FILE_NAME)
+}
+
+/// Reads the settings file. Without one the default settings, which look for a
+/// Quest mounted through gvfs, are used.
+pub fn load_settings() -> Result<Settings, LibraryError> {
+    let contents = match fs::read_to_string(settings_path()) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(err) => return Err(LibraryError::ReadSettings(err)),
+    };
+
+    serde_json::from_str(&contents).map_err(LibraryError::ParseSettings)
+}
+
+pub fn save_settings(settings: &Settings) -> Result<(), LibraryError> {
+    let path = settings_path();
+    let serialized =
+        serde_json::to_string_pretty(settings).map_err(LibraryError::SerializeSettings)?;
+
+    path.parent()
+        .map(fs::create_dir_all)
+        .unwrap_or(Ok(()))
+        .and_then(|_| fs::write(&path, serialized))
+        .map_err(LibraryError::WriteSettings)
+}
+
+fn find_device_folder(base: &Path, device: &str) -> Option<PathBuf> {
+    let device = device.to_lowercase();
+
+    fs::read_dir(base)
+        .ok()?
+        .flatten()
+        .find(|dir_entry| {
+            dir_entry
+                .file_name()
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&device)
+        })
+        .map(|dir_entry| dir_entry.path())
+}
@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    backend::{LibraryBackend, WatchPaths},
+    playlist_format::PlaylistFormat,
+};
+
+/// How often the polling fallback looks at the directories.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a level folder has to stay quiet before it is reported, so a song
+/// still being copied is read once it is complete.
+const LEVEL_SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// Something under the watched directories changed. The consumer re-reads the
+/// folder or file to find out whether it was added, modified or removed.
+#[derive(PartialEq, Eq, Debug)]
+pub enum LibraryChange {
+    Level(String),
+    Playlist(String),
+}
+
+/// Watches the level and playlist directories of a backend.
+///
+/// Uses inotify where it works. GVFS mounts don't deliver inotify events, so they
+/// are polled instead; polling only looks at the top level of the songs
+/// directory, so levels replaced in place are picked up by the next rescan.
+pub struct LibraryWatcher {
+    _watcher: Box<dyn Watcher + Send>,
+    receiver: Receiver<notify::Result<Event>>,
+    paths: WatchPaths,
+    /// Level folders that changed, with the time of their latest event.
+    settling_levels: HashMap<String, Instant>,
+    pub polling: bool,
+}
+
+impl LibraryWatcher {
+    pub fn start(backend: &dyn LibraryBackend) -> Option<notify::Result<Self>> {
+        Some(Self::watch(backend.watch_paths()?))
+    }
+
+    fn watch(paths: WatchPaths) -> notify::Result<Self> {
+        let is_gvfs = paths
+            .playlists
+            .components()
+            .any(|component| component.as_os_str() == "gvfs");
+
+        let watcher = if is_gvfs {
+            None
+        } else {
+            Self::watch_with_inotify(&paths).ok()
+        };
+
+        let (watcher, receiver, polling) = match watcher {
+            Some((watcher, receiver)) => (watcher, receiver, false),
+            None => {
+                let (watcher, receiver) = Self::watch_with_polling(&paths)?;
+                (watcher, receiver, true)
+            }
+        };
+
+        Ok(LibraryWatcher {
+            _watcher: watcher,
+            receiver,
+            paths,
+            settling_levels: HashMap::new(),
+            polling,
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn watch_with_inotify(
+        paths: &WatchPaths,
+    ) -> notify::Result<(Box<dyn Watcher + Send>, Receiver<notify::Result<Event>>)> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = RecommendedWatcher::new(sender, Config::default())?;
+        for (path, _) in paths.levels.iter() {
+            // A missing WIP folder only means there are no WIP levels.
+            if path.is_dir() {
+                watcher.watch(path, RecursiveMode::Recursive)?;
+            }
+        }
+        watcher.watch(&paths.playlists, RecursiveMode::NonRecursive)?;
+        Ok((Box::new(watcher), receiver))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn watch_with_polling(
+        paths: &WatchPaths,
+    ) -> notify::Result<(Box<dyn Watcher + Send>, Receiver<notify::Result<Event>>)> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher =
+            PollWatcher::new(sender, Config::default().with_poll_interval(POLL_INTERVAL))?;
+        for (path, _) in paths.levels.iter() {
+            if path.is_dir() {
+                watcher.watch(path, RecursiveMode::NonRecursive)?;
+            }
+        }
+        watcher.watch(&paths.playlists, RecursiveMode::NonRecursive)?;
+        Ok((Box::new(watcher), receiver))
+    }
+
+    /// Changes seen since the last call, without repeats. Level folders are held
+    /// back until they settled, see `LEVEL_SETTLE_TIME`.
+    pub fn poll(&mut self) -> Vec<LibraryChange> {
+        let mut changes = Vec::<LibraryChange>::new();
+        let now = Instant::now();
+
+        for event in self.receiver.try_iter().flatten() {
+            for path in event.paths.iter() {
+                match self.classify(path) {
+                    Some(LibraryChange::Level(folder)) => {
+                        self.settling_levels.insert(folder, now);
+                    }
+                    Some(change) if !changes.contains(&change) => changes.push(change),
+                    _ => {}
+                }
+            }
+        }
+
+        self.settling_levels.retain(|folder, changed| {
+            let settled = now.duration_since(*changed) >= LEVEL_SETTLE_TIME;
+            if settled {
+                changes.push(LibraryChange::Level(folder.to_string()));
+            }
+            !settled
+        });
+
+        changes
+    }
+
+    fn classify(&self, path: &Path) -> Option<LibraryChange> {
+        for (levels_path, prefix) in self.paths.levels.iter() {
+            if let Ok(relative) = path.strip_prefix(levels_path) {
+                let folder = relative.components().next()?.as_os_str().to_str()?;
+                return Some(LibraryChange::Level(format!("{}{}", prefix, folder)));
+            }
+        }
+
+        let file_name = path.strip_prefix(&self.paths.playlists).ok()?.to_str()?;
+        // Skips the temporary files written while saving, and covers or other
+        // files that aren't playlists.
+        if file_name.starts_with('.') || PlaylistFormat::from_file_name(file_name).is_none() {
+            return None;
+        }
+
+        Some(LibraryChange::Playlist(file_name.to_string()))
+    }
+}
@@ -0,0 +1,125 @@
+use std::{fs, path::Path};
+
+use playlist_creator::{
+    bmbf_utils::CustomLevel,
+    info_dat::{detect_version, parse_info_dat, InfoDatVersion},
+};
+
+const V2_FOLDER: &str = "1a2b (Song - Mapper)";
+const V4_FOLDER: &str = "3c4d (V4 Song - Mappers)";
+
+fn read_info_dat(folder: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/levels")
+        .join(folder)
+        .join("Info.dat");
+    fs::read_to_string(path).unwrap()
+}
+
+/// One line per beatmap: characteristic, difficulty, rank, files and note jump speed.
+fn beatmaps(level: &CustomLevel) -> Vec<String> {
+    level
+        .beatmaps()
+        .map(|(characteristic, beatmap)| {
+            format!(
+                "{} {} {} {} {} {}",
+                characteristic,
+                beatmap.difficulty,
+                beatmap.difficulty_rank,
+                beatmap.file_name,
+                beatmap.lightshow_file_name.as_deref().unwrap_or("-"),
+                beatmap.note_jump_speed
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn detects_version() {
+    assert_eq!(
+        detect_version(&read_info_dat(V2_FOLDER)).unwrap(),
+        InfoDatVersion::V2
+    );
+    assert_eq!(
+        detect_version(&read_info_dat(V4_FOLDER)).unwrap(),
+        InfoDatVersion::V4
+    );
+    // Early maps were saved without any version.
+    assert_eq!(detect_version("{}").unwrap(), InfoDatVersion::V2);
+    assert!(detect_version("not json").is_err());
+}
+
+#[test]
+fn parses_v2() {
+    let level = parse_info_dat(&read_info_dat(V2_FOLDER)).unwrap();
+
+    assert_eq!(level.version, "2.0.0");
+    assert_eq!(level.song_name, "Fixture Song");
+    assert_eq!(level.song_sub_name, "Extended Mix");
+    assert_eq!(level.song_author, "Fixture Artist");
+    assert_eq!(level.level_author, "Fixture Mapper");
+    assert_eq!(level.cover_image_filename, "cover.jpg");
+    assert_eq!(level.beats_per_minute, 174.0);
+    assert_eq!(level.audio_data_file_name, None);
+    assert_eq!(
+        beatmaps(&level),
+        vec![
+            "Standard Expert 7 ExpertStandard.dat - 16",
+            "Standard ExpertPlus 9 ExpertPlusStandard.dat - 19",
+            "OneSaber Expert 7 ExpertOneSaber.dat - 16",
+        ]
+    );
+    assert_eq!(
+        level.hashed_files(),
+        vec![
+            "ExpertStandard.dat",
+            "ExpertPlusStandard.dat",
+            "ExpertOneSaber.dat"
+        ]
+    );
+}
+
+#[test]
+fn parses_v4() {
+    let level = parse_info_dat(&read_info_dat(V4_FOLDER)).unwrap();
+
+    assert_eq!(level.version, "4.0.1");
+    assert_eq!(level.song_name, "Fixture V4 Song");
+    assert_eq!(level.song_sub_name, "Remastered");
+    assert_eq!(level.song_author, "Fixture V4 Artist");
+    assert_eq!(level.cover_image_filename, "cover.png");
+    assert_eq!(level.beats_per_minute, 128.0);
+    assert_eq!(level.audio_data_file_name.as_deref(), Some("BPMInfo.dat"));
+    assert_eq!(
+        beatmaps(&level),
+        vec![
+            "Standard Hard 5 HardStandard.dat Lightshow.dat 14",
+            "Standard ExpertPlus 9 ExpertPlusStandard.dat Lightshow.dat 20",
+            "Lawless Expert 7 ExpertLawless.dat Lightshow.dat 18",
+        ]
+    );
+}
+
+#[test]
+fn merges_v4_mappers() {
+    let level = parse_info_dat(&read_info_dat(V4_FOLDER)).unwrap();
+
+    // Each mapper once, in the order they first appear; lighters aren't mappers.
+    assert_eq!(level.level_author, "Alpha, Beta");
+}
+
+#[test]
+fn hashes_v4_audio_data_and_lightshow_once() {
+    let level = parse_info_dat(&read_info_dat(V4_FOLDER)).unwrap();
+
+    assert_eq!(
+        level.hashed_files(),
+        vec![
+            "BPMInfo.dat",
+            "HardStandard.dat",
+            "Lightshow.dat",
+            "ExpertPlusStandard.dat",
+            "ExpertLawless.dat"
+        ]
+    );
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use playlist_creator::{
+    backend::{LibraryBackend, LocalBackend},
+    bmbf_utils::{compute_level_hash, get_custom_level},
+    error::LoadReport,
+    info_dat::parse_info_dat,
+};
+
+/// Named the way BeatSaver downloads are, so the folder name is no hash.
+const LEVEL_FOLDER: &str = "1a2b (Song - Mapper)";
+/// SHA-1 over Info.dat and the three difficulty files, worked out separately.
+const LEVEL_HASH: &str = "86ADB578FEC6A2F992BF682F779A1EDAFC4D82FB";
+
+fn fixtures() -> LocalBackend {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    LocalBackend::new(root.join("levels"), root.join("playlists"))
+}
+
+#[test]
+fn hashes_info_dat_and_difficulty_files() {
+    let backend = fixtures();
+    let info_dat = backend.read_info_dat(LEVEL_FOLDER).unwrap();
+    let level = parse_info_dat(&info_dat).unwrap();
+
+    let hash = compute_level_hash(&backend, LEVEL_FOLDER, &info_dat, &level).unwrap();
+
+    assert_eq!(hash, LEVEL_HASH);
+}
+
+#[test]
+fn level_hash_replaces_folder_name() {
+    let mut report = LoadReport::default();
+
+    let level = get_custom_level(&fixtures(), LEVEL_FOLDER, &mut report).unwrap();
+
+    assert!(report.is_empty());
+    assert_eq!(level.hash.as_deref(), Some(LEVEL_HASH));
+    assert_eq!(level.folder.as_deref(), Some(LEVEL_FOLDER));
+}
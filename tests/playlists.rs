@@ -0,0 +1,128 @@
+use std::{fs, path::PathBuf};
+
+use playlist_creator::playlist_format::{decode_image_string, decode_playlist, encode_playlist};
+
+fn playlists_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/playlists")
+}
+
+/// Playlists saved by PlaylistManager, downloaded from BeatSaver and older tools.
+fn json_playlists() -> Vec<(String, Vec<u8>)> {
+    let mut playlists: Vec<(String, Vec<u8>)> = fs::read_dir(playlists_dir())
+        .unwrap()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let is_json = file_name.ends_with(".json") || file_name.ends_with(".bplist");
+            is_json.then(|| (file_name, fs::read(entry.path()).unwrap()))
+        })
+        .collect();
+    playlists.sort();
+    playlists
+}
+
+#[test]
+fn round_trips_json_playlists() {
+    let playlists = json_playlists();
+    assert!(playlists.len() >= 3);
+
+    for (file_name, bytes) in playlists {
+        let (mut playlist, _) = decode_playlist(&file_name, &bytes).unwrap();
+        playlist.file_name = file_name.to_string();
+        let (encoded, _) = encode_playlist(&playlist).unwrap();
+
+        let original: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let round_tripped: serde_json::Value = serde_json::from_slice(&encoded).unwrap();
+        assert_eq!(round_tripped, original, "{} changed", file_name);
+    }
+}
+
+#[test]
+fn keeps_unknown_fields() {
+    let bytes = fs::read(playlists_dir().join("Favourites.json")).unwrap();
+
+    let (playlist, _) = decode_playlist("Favourites.json", &bytes).unwrap();
+
+    assert_eq!(playlist.extra["allowDuplicates"], false);
+    assert_eq!(
+        playlist.songs[0].extra["dateAdded"],
+        "2023-04-02T18:21:44.3196711+02:00"
+    );
+    assert_eq!(
+        playlist.songs[1].extra["customData"]["requirements"][0],
+        "Noodle Extensions"
+    );
+    assert_eq!(playlist.songs[1].key.as_deref(), Some("2f1b"));
+}
+
+#[test]
+fn reads_songs_with_only_a_key() {
+    let file_name = "Old Ranked Pack.bplist";
+    let bytes = fs::read(playlists_dir().join(file_name)).unwrap();
+
+    let (playlist, _) = decode_playlist(file_name, &bytes).unwrap();
+
+    let song = &playlist.songs[2];
+    assert_eq!(song.key.as_deref(), Some("2f1b"));
+    assert_eq!(song.hash, "");
+    assert_eq!(song.name, "");
+}
+
+#[test]
+fn decodes_and_encodes_blist() {
+    let file_name = "Blister Mix.blist";
+    let bytes = fs::read(playlists_dir().join(file_name)).unwrap();
+
+    let (mut playlist, _) = decode_playlist(file_name, &bytes).unwrap();
+
+    assert_eq!(playlist.title, "Blister Mix");
+    assert_eq!(playlist.author.as_deref(), Some("Blister"));
+    assert_eq!(playlist.description.as_deref(), Some("Made with Blister"));
+    let hashes: Vec<&str> = playlist
+        .songs
+        .iter()
+        .map(|song| song.hash.as_str())
+        .collect();
+    // The second map only has a level ID, the third only a BeatSaver key.
+    assert_eq!(
+        hashes,
+        vec![
+            "86ADB578FEC6A2F992BF682F779A1EDAFC4D82FB",
+            "B3F1C2D4E5A6978877665544332211FFEEDDCCBB",
+            ""
+        ]
+    );
+    assert_eq!(playlist.songs[2].key.as_deref(), Some("2f1b"));
+    assert_eq!(playlist.songs[0].difficulties[0].name, "ExpertPlus");
+    assert_eq!(playlist.songs[0].extra["dateAdded"], "2023-05-01T12:00:00Z");
+    let cover = decode_image_string(playlist.image.as_deref().unwrap()).unwrap();
+    assert!(cover.starts_with(b"\x89PNG"));
+
+    playlist.file_name = file_name.to_string();
+    let (encoded, _) = encode_playlist(&playlist).unwrap();
+    assert!(encoded.starts_with(b"PK"));
+    let (decoded, _) = decode_playlist(file_name, &encoded).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&decoded).unwrap(),
+        serde_json::to_value(&playlist).unwrap()
+    );
+}
+
+#[test]
+fn reads_beatsaver_image_key() {
+    let file_name = "beatsaver-curated-42.bplist";
+    let bytes = fs::read(playlists_dir().join(file_name)).unwrap();
+
+    let (mut playlist, _) = decode_playlist(file_name, &bytes).unwrap();
+
+    let cover = decode_image_string(playlist.image.as_deref().unwrap()).unwrap();
+    assert!(cover.starts_with(b"\x89PNG"));
+    assert!(!playlist.extra.contains_key("image"));
+
+    playlist.file_name = file_name.to_string();
+    let (encoded, _) = encode_playlist(&playlist).unwrap();
+    let encoded: serde_json::Value = serde_json::from_slice(&encoded).unwrap();
+    assert!(encoded.get("imageString").is_none());
+    assert_eq!(encoded["image"], playlist.image.unwrap());
+}